@@ -123,6 +123,16 @@ CREATE TABLE IF NOT EXISTS player_skills (
     FOREIGN KEY (idnum) REFERENCES player_main(idnum) ON DELETE CASCADE
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
 
+-- Player aliases table
+CREATE TABLE IF NOT EXISTS player_aliases (
+    idnum INT NOT NULL,
+    seq INT NOT NULL,
+    alias VARCHAR(32) NOT NULL,
+    replacement VARCHAR(255) NOT NULL,
+    PRIMARY KEY (idnum, alias),
+    FOREIGN KEY (idnum) REFERENCES player_main(idnum) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
 -- Add a test immortal character for initial login
 INSERT INTO player_main (idnum, name, pwd, level, sex, class, race, deity, hometown, birth, played, last_logon, 
                         hit, max_hit, mana, max_mana, move, max_move, gold, exp, 
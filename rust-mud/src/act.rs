@@ -0,0 +1,284 @@
+// act() messages
+//
+// Port of act and perform_act from /web/deltamud/src/comm.c. A message is
+// written once with $-codes and rendered separately for each reader, so
+// the same "$n smiles at $N." reads right whoever sees it:
+//
+//   $n / $N   name of the actor / victim, as the reader sees them
+//   $m / $M   him, her or it
+//   $s / $S   his, her or its
+//   $e / $E   he, she or it
+//   $p / $P   short description of the object / second object
+//   $o        first keyword of the object
+//   $a        "a" or "an" to go before the object
+//   $t / $T   the text argument (a body part, for socials)
+//   $$        a literal '$'
+//
+// Names and objects the reader can't see come out as "someone" and
+// "something". Who gets the message is the TO_ target: the actor, the
+// victim, everyone else in the room, or everyone else but the victim.
+
+use crate::character::Character;
+use crate::object::{ExtraFlags, Object};
+use crate::types::Position;
+use log::warn;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+// act() targets. TO_SLEEP may be or'd onto TO_CHAR or TO_VICT to reach
+// them even asleep.
+pub const TO_ROOM: u8 = 1;
+pub const TO_VICT: u8 = 2;
+pub const TO_NOTVICT: u8 = 3;
+pub const TO_CHAR: u8 = 4;
+pub const TO_SLEEP: u8 = 128;
+
+/// What's printed for a $-code whose character, object or text wasn't
+/// supplied (ACTNULL).
+const ACT_NULL: &str = "<NULL>";
+
+/// Everything an act() message can refer to.
+pub struct ActArgs<'a> {
+    pub ch: &'a Character,
+    pub obj: Option<&'a Object>,
+    pub vict: Option<&'a Character>,
+    /// The second object, for $P (a container, say)
+    pub vict_obj: Option<&'a Object>,
+    pub text: Option<&'a str>,
+}
+
+impl<'a> ActArgs<'a> {
+    pub fn new(ch: &'a Character) -> Self {
+        ActArgs { ch, obj: None, vict: None, vict_obj: None, text: None }
+    }
+
+    pub fn obj(mut self, obj: &'a Object) -> Self {
+        self.obj = Some(obj);
+        self
+    }
+
+    pub fn vict(mut self, vict: &'a Character) -> Self {
+        self.vict = Some(vict);
+        self
+    }
+
+    pub fn text(mut self, text: &'a str) -> Self {
+        self.text = Some(text);
+        self
+    }
+}
+
+/// How `to` sees `ch` named (PERS).
+pub fn pers<'a>(ch: &'a Character, to: &Character) -> &'a str {
+    if to.can_see(ch) {
+        ch.act_name()
+    } else if ch.is_immortal() {
+        "A Mystical Being"
+    } else {
+        "someone"
+    }
+}
+
+/// How `to` sees an object described (OBJS).
+fn objs<'a>(obj: &'a Object, to: &Character) -> &'a str {
+    if can_see_obj(to, obj) {
+        &obj.short_description
+    } else {
+        "something"
+    }
+}
+
+/// Whether `ch` can see `obj` (CAN_SEE_OBJ).
+pub fn can_see_obj(ch: &Character, obj: &Object) -> bool {
+    ch.is_immortal() || (!ch.is_blind() && (!obj.extra_flags.contains(ExtraFlags::INVISIBLE) || ch.detects_invis()))
+}
+
+/// Render an act() message for everyone `to` selects (act). `people` is
+/// everyone in the actor's room other than the actor, the victim
+/// included if they're there. Returns each reader's id and their text.
+pub fn act(msg: &str, hide_invisible: bool, args: &ActArgs, to: u8, people: &[&Character]) -> Vec<(u64, String)> {
+    if msg.is_empty() {
+        return Vec::new();
+    }
+    let sleep = to & TO_SLEEP != 0;
+    // SENDOK: awake, unless TO_SLEEP
+    let sendok = |reader: &Character| sleep || reader.position > Position::Sleeping;
+
+    match to & !TO_SLEEP {
+        TO_CHAR => {
+            if sendok(args.ch) {
+                return vec![(args.ch.id, perform_act(msg, args, args.ch))];
+            }
+            Vec::new()
+        }
+        TO_VICT => match args.vict {
+            Some(vict) if sendok(vict) => vec![(vict.id, perform_act(msg, args, vict))],
+            _ => Vec::new(),
+        },
+        target => people.iter()
+            .filter(|reader| reader.id != args.ch.id)
+            .filter(|reader| target == TO_ROOM || args.vict.is_none_or(|v| v.id != reader.id))
+            .filter(|reader| sendok(reader) && (!hide_invisible || reader.can_see(args.ch)))
+            .map(|reader| (reader.id, perform_act(msg, args, reader)))
+            .collect(),
+    }
+}
+
+/// An act() call to deliver later, for code that decides what to say
+/// while it holds world locks (combat rounds, spells).
+pub struct Act {
+    pub msg: String,
+    pub hide_invisible: bool,
+    pub ch: Arc<RwLock<Character>>,
+    pub obj: Option<Arc<RwLock<Object>>>,
+    pub vict: Option<Arc<RwLock<Character>>>,
+    pub vict_obj: Option<Arc<RwLock<Object>>>,
+    pub text: Option<String>,
+    pub to: u8,
+}
+
+impl Act {
+    pub fn new(msg: impl Into<String>, ch: &Arc<RwLock<Character>>, to: u8) -> Self {
+        Act {
+            msg: msg.into(),
+            hide_invisible: false,
+            ch: ch.clone(),
+            obj: None,
+            vict: None,
+            vict_obj: None,
+            text: None,
+            to,
+        }
+    }
+
+    pub fn obj(mut self, obj: &Arc<RwLock<Object>>) -> Self {
+        self.obj = Some(obj.clone());
+        self
+    }
+
+    pub fn vict_obj(mut self, obj: &Arc<RwLock<Object>>) -> Self {
+        self.vict_obj = Some(obj.clone());
+        self
+    }
+
+    pub fn vict(mut self, vict: &Arc<RwLock<Character>>) -> Self {
+        self.vict = Some(vict.clone());
+        self
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn hide_invisible(mut self) -> Self {
+        self.hide_invisible = true;
+        self
+    }
+
+    /// Render for everyone it reaches. Takes the room and character
+    /// locks itself, so call it with none held.
+    pub fn render(&self) -> Vec<(u64, String)> {
+        let same = |a: &Arc<RwLock<Character>>, b: &Arc<RwLock<Character>>| Arc::ptr_eq(a, b);
+        let vict_is_ch = self.vict.as_ref().is_some_and(|v| same(v, &self.ch));
+
+        let room = self.ch.read().in_room.as_ref().and_then(|w| w.upgrade());
+        let mut vict_in_room = false;
+        let others: Vec<Arc<RwLock<Character>>> = match &room {
+            Some(room) => room.read().people.iter()
+                .filter_map(|w| w.upgrade())
+                .filter(|p| !same(p, &self.ch))
+                .filter(|p| match &self.vict {
+                    Some(v) if same(p, v) => {
+                        vict_in_room = true;
+                        false
+                    }
+                    _ => true,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let ch = self.ch.read();
+        let vict_guard = self.vict.as_ref().filter(|_| !vict_is_ch).map(|v| v.read());
+        let vict = if vict_is_ch { Some(&*ch) } else { vict_guard.as_deref() };
+        let guards: Vec<_> = others.iter().map(|p| p.read()).collect();
+        let mut people: Vec<&Character> = guards.iter().map(|g| &**g).collect();
+        if let (true, Some(v)) = (vict_in_room, vict_guard.as_deref()) {
+            people.push(v);
+        }
+
+        let obj = self.obj.as_ref().map(|o| o.read());
+        let vict_obj = self.vict_obj.as_ref().map(|o| o.read());
+        let args = ActArgs {
+            ch: &ch,
+            obj: obj.as_deref(),
+            vict,
+            vict_obj: vict_obj.as_deref(),
+            text: self.text.as_deref(),
+        };
+        act(&self.msg, self.hide_invisible, &args, self.to, &people)
+    }
+}
+
+/// Render `msg` for the reader `to`, capitalized like CAP().
+pub fn perform_act(msg: &str, args: &ActArgs, to: &Character) -> String {
+    let mut out = String::with_capacity(msg.len() + 32);
+    let mut chars = msg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let code = match chars.next() {
+            Some(code) => code,
+            None => break,
+        };
+        let vict = args.vict;
+        match code {
+            'n' => out.push_str(pers(args.ch, to)),
+            'N' => out.push_str(vict.map_or(ACT_NULL, |v| pers(v, to))),
+            'm' => out.push_str(args.ch.him_her()),
+            'M' => out.push_str(vict.map_or(ACT_NULL, |v| v.him_her())),
+            's' => out.push_str(args.ch.his_her()),
+            'S' => out.push_str(vict.map_or(ACT_NULL, |v| v.his_her())),
+            'e' => out.push_str(args.ch.he_she()),
+            'E' => out.push_str(vict.map_or(ACT_NULL, |v| v.he_she())),
+            'p' => out.push_str(args.obj.map_or(ACT_NULL, |o| objs(o, to))),
+            'P' => out.push_str(args.vict_obj.map_or(ACT_NULL, |o| objs(o, to))),
+            'o' => out.push_str(args.obj.map_or(ACT_NULL, |o| o.name.split_whitespace().next().unwrap_or(""))),
+            'a' => out.push_str(args.obj.map_or(ACT_NULL, |o| an(&o.name))),
+            't' | 'T' => out.push_str(args.text.unwrap_or(ACT_NULL)),
+            '$' => out.push('$'),
+            other => {
+                warn!("Illegal $-code to act(): ${} in \"{}\"", other, msg);
+            }
+        }
+    }
+
+    cap(out)
+}
+
+/// Quote text typed by a player so act() prints it as it stands rather
+/// than reading '$' in it as a code.
+pub fn escape(text: &str) -> String {
+    text.replace('$', "$$")
+}
+
+/// "an" before a vowel, otherwise "a" (SANA).
+pub fn an(name: &str) -> &'static str {
+    match name.chars().next().map(|c| c.to_ascii_lowercase()) {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => "an",
+        _ => "a",
+    }
+}
+
+/// Upper-case the first letter (CAP).
+fn cap(mut s: String) -> String {
+    if let Some(first) = s.chars().next() {
+        let upper: String = first.to_uppercase().collect();
+        s.replace_range(..first.len_utf8(), &upper);
+    }
+    s
+}
@@ -0,0 +1,72 @@
+// Player aliases
+//
+// Port of the alias routines in /web/deltamud/src/interpreter.c. An alias
+// whose replacement has no ';' or '$' is simple: typing it runs the
+// replacement instead, and anything typed after the alias is dropped. A
+// complex alias is split on ';' into several commands, with $1 .. $9
+// standing for the words typed after the alias and $* for all of them.
+
+pub const ALIAS_SEP_CHAR: char = ';';
+pub const ALIAS_VAR_CHAR: char = '$';
+pub const ALIAS_GLOB_CHAR: char = '*';
+
+/// Most words of the original line $1 .. $9 can refer to.
+const NUM_TOKENS: usize = 9;
+
+#[derive(Debug, Clone)]
+pub struct Alias {
+    pub name: String,
+    pub replacement: String,
+}
+
+impl Alias {
+    pub fn new(name: &str, replacement: &str) -> Self {
+        Alias {
+            name: name.to_lowercase(),
+            // delete_doubledollar: "$$" is how players type a literal '$'
+            replacement: replacement.replace("$$", "$"),
+        }
+    }
+
+    pub fn is_complex(&self) -> bool {
+        self.replacement.contains(ALIAS_SEP_CHAR) || self.replacement.contains(ALIAS_VAR_CHAR)
+    }
+
+    /// The commands this alias runs when typed with `args` after it
+    /// (perform_complex_alias). A simple alias always gives one line.
+    pub fn expand(&self, args: &str) -> Vec<String> {
+        if !self.is_complex() {
+            return vec![self.replacement.clone()];
+        }
+
+        let tokens: Vec<&str> = args.split_whitespace().take(NUM_TOKENS).collect();
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut chars = self.replacement.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                ALIAS_SEP_CHAR => lines.push(std::mem::take(&mut line)),
+                ALIAS_VAR_CHAR => match chars.next() {
+                    Some(ALIAS_GLOB_CHAR) => line.push_str(args),
+                    Some(d @ '1'..='9') => {
+                        if let Some(token) = tokens.get(d as usize - '1' as usize) {
+                            line.push_str(token);
+                        }
+                    }
+                    // Anything else after '$' stands for itself
+                    Some(other) => line.push(other),
+                    None => line.push(ALIAS_VAR_CHAR),
+                },
+                _ => line.push(c),
+            }
+        }
+        lines.push(line);
+        lines
+    }
+}
+
+/// The alias named exactly `word`, if any (find_alias).
+pub fn find<'a>(aliases: &'a [Alias], word: &str) -> Option<&'a Alias> {
+    aliases.iter().find(|a| a.name == word)
+}
@@ -0,0 +1,252 @@
+// Site bans
+//
+// Port of /web/deltamud/src/ban.c. lib/etc/badsites holds one ban per
+// line: the level, the site mask, when it was set (unix time) and who set
+// it. Masks may use * and ? wildcards. We only know a client's IP, not a
+// resolved hostname, so masks are matched against the address.
+//
+// The levels, from weakest: "new" stops characters being created from the
+// site, "select" also turns away existing characters without the SITEOK
+// flag, and "all" refuses the connection outright.
+
+use chrono::{TimeZone, Utc};
+use log::{error, info, warn};
+use parking_lot::RwLock;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+pub const BAN_FILE: &str = "etc/badsites";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BanType {
+    Not,
+    New,
+    Select,
+    All,
+}
+
+impl BanType {
+    pub fn name(self) -> &'static str {
+        match self {
+            BanType::Not => "no",
+            BanType::New => "new",
+            BanType::Select => "select",
+            BanType::All => "all",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BanType> {
+        match name.to_lowercase().as_str() {
+            "no" => Some(BanType::Not),
+            "new" => Some(BanType::New),
+            "select" => Some(BanType::Select),
+            "all" => Some(BanType::All),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BanEntry {
+    pub site: String,
+    pub ban_type: BanType,
+    /// Unix time the ban was set, 0 if unknown
+    pub date: i64,
+    /// Who set it
+    pub name: String,
+}
+
+pub struct BanList {
+    entries: Vec<BanEntry>,
+    path: PathBuf,
+}
+
+impl BanList {
+    /// Read lib/etc/badsites. A missing file is an empty list.
+    pub fn load(lib_path: &str) -> Self {
+        let path = Path::new(lib_path).join(BAN_FILE);
+        let mut entries = Vec::new();
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                for line in text.lines() {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    if fields.len() != 4 {
+                        continue;
+                    }
+                    let ban_type = match BanType::from_name(fields[0]) {
+                        Some(ban_type) => ban_type,
+                        None => {
+                            warn!("Unknown ban type '{}' in {}", fields[0], path.display());
+                            continue;
+                        }
+                    };
+                    entries.push(BanEntry {
+                        site: fields[1].to_lowercase(),
+                        ban_type,
+                        date: fields[2].parse().unwrap_or(0),
+                        name: fields[3].to_string(),
+                    });
+                }
+            }
+            Err(e) => warn!("Unable to open banfile {}: {}", path.display(), e),
+        }
+
+        BanList { entries, path }
+    }
+
+    /// Write the list back out (write_ban_list).
+    fn save(&self) {
+        let text: String = self.entries.iter()
+            .map(|e| format!("{} {} {} {}\n", e.ban_type.name(), e.site, e.date, e.name))
+            .collect();
+        if let Err(e) = std::fs::write(&self.path, text) {
+            error!("Unable to write banfile {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn entries(&self) -> &[BanEntry] {
+        &self.entries
+    }
+
+    /// The strongest ban matching an address (isbanned).
+    pub fn is_banned(&self, ip: IpAddr) -> BanType {
+        let host = ip.to_canonical().to_string();
+        self.entries.iter()
+            .filter(|e| wildmatch(&e.site, &host))
+            .map(|e| e.ban_type)
+            .max()
+            .unwrap_or(BanType::Not)
+    }
+
+    /// Add a ban and save. Fails if the site is already banned.
+    pub fn ban(&mut self, site: &str, ban_type: BanType, by: &str) -> Result<(), &'static str> {
+        let site = site.to_lowercase();
+        if self.entries.iter().any(|e| e.site == site) {
+            return Err("That site has already been banned -- unban it to change the ban type.");
+        }
+        self.entries.push(BanEntry {
+            site,
+            ban_type,
+            date: Utc::now().timestamp(),
+            name: by.to_string(),
+        });
+        self.save();
+        Ok(())
+    }
+
+    /// Lift a ban and save, returning what was removed.
+    pub fn unban(&mut self, site: &str) -> Option<BanEntry> {
+        let pos = self.entries.iter().position(|e| e.site.eq_ignore_ascii_case(site))?;
+        let entry = self.entries.remove(pos);
+        self.save();
+        Some(entry)
+    }
+}
+
+impl BanEntry {
+    /// The date column of the ban listing, like asctime cut to 10 chars.
+    pub fn date_string(&self) -> String {
+        match Utc.timestamp_opt(self.date, 0).single() {
+            Some(date) if self.date != 0 => date.format("%a %b %e").to_string(),
+            _ => "Unknown".to_string(),
+        }
+    }
+}
+
+/// Whether a connection from `addr` should be turned away before it gets
+/// a welcome banner, i.e. its site has an "all" ban.
+pub fn refused(bans: &RwLock<BanList>, addr: SocketAddr) -> bool {
+    if bans.read().is_banned(addr.ip()) == BanType::All {
+        info!("Connection attempt denied from [{}]", addr.ip());
+        return true;
+    }
+    false
+}
+
+/// Match a site mask where * is any run of characters and ? is any one.
+fn wildmatch(mask: &str, s: &str) -> bool {
+    let mask: Vec<char> = mask.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    let (mut m, mut i) = (0, 0);
+    // Where the last * was, and how much of s it had swallowed
+    let mut star: Option<(usize, usize)> = None;
+
+    while i < s.len() {
+        if m < mask.len() && (mask[m] == '?' || mask[m] == s[i]) {
+            m += 1;
+            i += 1;
+        } else if m < mask.len() && mask[m] == '*' {
+            star = Some((m, i));
+            m += 1;
+        } else if let Some((star_m, star_i)) = star {
+            m = star_m + 1;
+            i = star_i + 1;
+            star = Some((star_m, star_i + 1));
+        } else {
+            return false;
+        }
+    }
+    mask[m..].iter().all(|&c| c == '*')
+}
+
+/// Sites allowed to have more than one character playing at once, for
+/// players behind a shared address. Kept in lib/etc/multiok, one mask per
+/// line. The C code cleared characters one by one with PLR_MULTIOK;
+/// clearing the site covers alts made later too.
+pub struct MultiplayExemptions {
+    sites: Vec<String>,
+    path: PathBuf,
+}
+
+pub const MULTIOK_FILE: &str = "etc/multiok";
+
+impl MultiplayExemptions {
+    /// Read lib/etc/multiok. A missing file is an empty list.
+    pub fn load(lib_path: &str) -> Self {
+        let path = Path::new(lib_path).join(MULTIOK_FILE);
+        let sites = match std::fs::read_to_string(&path) {
+            Ok(text) => text.split_whitespace().map(|s| s.to_lowercase()).collect(),
+            Err(_) => Vec::new(),
+        };
+        MultiplayExemptions { sites, path }
+    }
+
+    fn save(&self) {
+        let text: String = self.sites.iter().map(|s| format!("{}\n", s)).collect();
+        if let Err(e) = std::fs::write(&self.path, text) {
+            error!("Unable to write {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn sites(&self) -> &[String] {
+        &self.sites
+    }
+
+    pub fn is_exempt(&self, ip: IpAddr) -> bool {
+        let host = ip.to_canonical().to_string();
+        self.sites.iter().any(|site| wildmatch(site, &host))
+    }
+
+    /// Add a site and save. Returns false if it was already listed.
+    pub fn add(&mut self, site: &str) -> bool {
+        let site = site.to_lowercase();
+        if self.sites.contains(&site) {
+            return false;
+        }
+        self.sites.push(site);
+        self.save();
+        true
+    }
+
+    /// Remove a site and save. Returns false if it wasn't listed.
+    pub fn remove(&mut self, site: &str) -> bool {
+        let before = self.sites.len();
+        self.sites.retain(|s| !s.eq_ignore_ascii_case(site));
+        if self.sites.len() == before {
+            return false;
+        }
+        self.save();
+        true
+    }
+}
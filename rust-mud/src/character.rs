@@ -1,10 +1,15 @@
 use crate::types::*;
 use crate::room::Room;
 use crate::object::Object;
+use crate::alias::Alias;
+use crate::magic::{AFF_BLIND, AFF_DETECT_INVIS, AFF_HIDE, AFF_INVISIBLE, AFF_SENSE_LIFE};
 use std::sync::{Arc, Weak};
 use parking_lot::RwLock;
 use chrono::{DateTime, Utc};
 
+/// PAGE_LENGTH from /web/deltamud/src/modify.c
+pub const DEFAULT_PAGE_LENGTH: u8 = 22;
+
 // Ability scores
 #[derive(Debug, Clone, Default, Copy)]
 pub struct Abilities {
@@ -46,6 +51,10 @@ pub struct PlayerData {
     pub time_played: i64,
     pub weight: u8,
     pub height: u8,
+    /// Lines per page of paged output; 0 turns paging off
+    pub page_length: u8,
+    /// Address the player last logged in from
+    pub host: String,
 }
 
 // Affect structure for spells/skills
@@ -90,6 +99,11 @@ pub struct Character {
     // Status
     pub position: Position,
     pub affected: Vec<Affect>,
+    // Pulses of lag before the next command runs
+    pub wait: u32,
+
+    // Aliases, newest first
+    pub aliases: Vec<Alias>,
     
     // Combat
     pub fighting: Option<Weak<RwLock<Character>>>,
@@ -128,6 +142,8 @@ impl Character {
                 time_played: 0,
                 weight: 150,
                 height: 170,
+                page_length: DEFAULT_PAGE_LENGTH,
+                host: String::new(),
             },
             real_abils: Abilities::default(),
             aff_abils: Abilities::default(),
@@ -148,6 +164,8 @@ impl Character {
             equipment: Default::default(),
             position: Position::Standing,
             affected: Vec::new(),
+            wait: 0,
+            aliases: Vec::new(),
             fighting: None,
             master: None,
             followers: Vec::new(),
@@ -181,6 +199,8 @@ impl Character {
                 time_played: 0,
                 weight: 150,
                 height: 170,
+                page_length: DEFAULT_PAGE_LENGTH,
+                host: String::new(),
             },
             real_abils: Abilities::default(),
             aff_abils: Abilities::default(),
@@ -189,6 +209,8 @@ impl Character {
             equipment: Default::default(),
             position: Position::Standing,
             affected: Vec::new(),
+            wait: 0,
+            aliases: Vec::new(),
             fighting: None,
             master: None,
             followers: Vec::new(),
@@ -203,10 +225,52 @@ impl Character {
         }
     }
 
+    /// Lag the character for `pulses` game pulses, during which queued
+    /// commands wait (WAIT_STATE in /web/deltamud/src/utils.h).
+    pub fn wait_state(&mut self, pulses: u32) {
+        self.wait = pulses;
+    }
+
     pub fn get_name(&self) -> &str {
         &self.player.name
     }
     
+    /// Possessive pronoun, CircleMUD's HSHR().
+    pub fn his_her(&self) -> &'static str {
+        match self.player.sex {
+            Gender::Male => "his",
+            Gender::Female => "her",
+            Gender::Neutral => "its",
+        }
+    }
+    
+    /// Subject pronoun, HSSH().
+    pub fn he_she(&self) -> &'static str {
+        match self.player.sex {
+            Gender::Male => "he",
+            Gender::Female => "she",
+            Gender::Neutral => "it",
+        }
+    }
+    
+    /// Object pronoun, HMHR().
+    pub fn him_her(&self) -> &'static str {
+        match self.player.sex {
+            Gender::Male => "him",
+            Gender::Female => "her",
+            Gender::Neutral => "it",
+        }
+    }
+    
+    /// The name act() messages use (GET_NAME): a mob's short
+    /// description, a player's bare name.
+    pub fn act_name(&self) -> &str {
+        match &self.short_desc {
+            Some(s) if self.is_npc && !s.is_empty() => s,
+            _ => &self.player.name,
+        }
+    }
+    
     pub fn get_title(&self) -> String {
         match &self.player.title {
             Some(title) => format!("{} {}", self.player.name, title),
@@ -215,16 +279,33 @@ impl Character {
     }
     
     pub fn is_immortal(&self) -> bool {
-        self.player.level >= 31
+        self.player.level >= LVL_IMMORT
+    }
+    
+    pub fn is_blind(&self) -> bool {
+        self.affect_flags & AFF_BLIND != 0
     }
     
-    pub fn can_see(&self, _target: &Character) -> bool {
-        // Simplified visibility check
-        if self.is_immortal() {
+    pub fn detects_invis(&self) -> bool {
+        self.affect_flags & AFF_DETECT_INVIS != 0
+    }
+    
+    /// Whether this character can see `target` (CAN_SEE in
+    /// /web/deltamud/src/utils.h). Rooms don't track light sources yet,
+    /// so darkness hides no one.
+    pub fn can_see(&self, target: &Character) -> bool {
+        if std::ptr::eq(self, target) || self.is_immortal() {
             return true;
         }
-        
-        // TODO: Add more visibility checks (light, invisibility, etc.)
+        if self.is_blind() {
+            return false;
+        }
+        if target.affect_flags & AFF_INVISIBLE != 0 && !self.detects_invis() {
+            return false;
+        }
+        if target.affect_flags & AFF_HIDE != 0 && self.affect_flags & AFF_SENSE_LIFE == 0 {
+            return false;
+        }
         true
     }
     
@@ -257,6 +338,8 @@ impl Character {
             equipment: Default::default(),
             position: self.position,
             affected: self.affected.clone(),
+            wait: 0,
+            aliases: self.aliases.clone(),
             fighting: None,
             master: None,
             followers: Vec::new(),
@@ -1,4 +1,6 @@
+use crate::act::{Act, TO_CHAR, TO_NOTVICT, TO_ROOM, TO_SLEEP, TO_VICT};
 use crate::character::Character;
+use crate::magic::{SPELL_FIREBALL, SPELL_MAGIC_MISSILE};
 use crate::room::Room;
 use crate::types::*;
 use std::sync::Arc;
@@ -16,38 +18,115 @@ pub struct DeathResult {
 
 // Combat-related constants
 pub const PULSE_VIOLENCE: u64 = 3;  // 3 seconds between combat rounds
+/// One combat round of lag in game pulses, PULSE_VIOLENCE as the C skills
+/// and spells pass it to WAIT_STATE.
+pub const WAIT_ROUND: u32 = 20;
 pub const WEAR_WIELD: usize = 16;
 
-// Damage types
-#[derive(Debug, Clone, Copy)]
-pub enum DamageType {
-    Hit,
-    Slash,
-    Pierce,
-    Bludgeon,
-    Fire,
-    Cold,
-    Lightning,
-    Acid,
-    Poison,
-}
-
-// Attack types for messages
+// Attack types for messages, in the order weapons name them with their
+// fourth value (attack_hit_text in /web/deltamud/src/fight.c)
 #[derive(Debug, Clone, Copy)]
 pub struct AttackType {
     pub singular: &'static str,
     pub plural: &'static str,
 }
 
+pub const TYPE_HIT: usize = 0;
+
 pub const ATTACK_TYPES: &[AttackType] = &[
     AttackType { singular: "hit", plural: "hits" },
+    AttackType { singular: "sting", plural: "stings" },
+    AttackType { singular: "whip", plural: "whips" },
     AttackType { singular: "slash", plural: "slashes" },
-    AttackType { singular: "pierce", plural: "pierces" },
-    AttackType { singular: "pound", plural: "pounds" },
-    AttackType { singular: "claw", plural: "claws" },
     AttackType { singular: "bite", plural: "bites" },
-    AttackType { singular: "sting", plural: "stings" },
+    AttackType { singular: "bludgeon", plural: "bludgeons" },
     AttackType { singular: "crush", plural: "crushes" },
+    AttackType { singular: "pound", plural: "pounds" },
+    AttackType { singular: "claw", plural: "claws" },
+    AttackType { singular: "maul", plural: "mauls" },
+    AttackType { singular: "thrash", plural: "thrashes" },
+    AttackType { singular: "pierce", plural: "pierces" },
+    AttackType { singular: "blast", plural: "blasts" },
+    AttackType { singular: "punch", plural: "punches" },
+    AttackType { singular: "stab", plural: "stabs" },
+];
+
+/// Weapon damage messages by severity, to the room, the attacker and the
+/// victim (dam_weapons in dam_message, /web/deltamud/src/fight.c). #w is
+/// the attack's singular and #W its plural.
+const DAM_WEAPONS: [[&str; 3]; 12] = [
+    ["$n tries to #w $N, but misses.",
+     "You try to #w $N, but miss.",
+     "$n tries to #w you, but misses."],
+    ["$n tickles $N as $e #W $M.",
+     "You tickle $N as you #w $M.",
+     "$n tickles you as $e #W you."],
+    ["$n barely #W $N.",
+     "You barely #w $N.",
+     "$n barely #W you."],
+    ["$n #W $N.",
+     "You #w $N.",
+     "$n #W you."],
+    ["$n #W $N hard.",
+     "You #w $N hard.",
+     "$n #W you hard."],
+    ["$n #W $N very hard.",
+     "You #w $N very hard.",
+     "$n #W you very hard."],
+    ["$n #W $N extremely hard.",
+     "You #w $N extremely hard.",
+     "$n #W you extremely hard."],
+    ["$n massacres $N to small fragments with $s #w.",
+     "You massacre $N to small fragments with your #w.",
+     "$n massacres you to small fragments with $s #w."],
+    ["$n OBLITERATES $N with $s deadly #w!!",
+     "You OBLITERATE $N with your deadly #w!!",
+     "$n OBLITERATES you with $s deadly #w!!"],
+    ["$n PULVERIZES $N to bits with $s deadly #w!!",
+     "You PULVERIZE $N to bits with your deadly #w!!",
+     "$n PULVERIZES you to bits with $s deadly #w!!"],
+    ["$n VAPORIZES $N with $s deadly #w!!",
+     "You VAPORIZE $N with your deadly #w!!",
+     "$n VAPORIZES you with $s deadly #w!!"],
+    ["$n ANNIHILATES $N to smithereens with $s deadly #w!!",
+     "You ANNIHILATE $N to smithereens with your deadly #w!!",
+     "$n ANNIHILATES you to smithereens with $s deadly #w!!"],
+];
+
+/// A spell's entry from lib/misc/messages: what the caster, the victim
+/// and the room see when it kills, misses and hits.
+struct SkillMessages {
+    spell: i32,
+    die: [&'static str; 3],
+    miss: [&'static str; 3],
+    hit: [&'static str; 3],
+}
+
+const SKILL_MESSAGES: &[SkillMessages] = &[
+    SkillMessages {
+        spell: SPELL_FIREBALL,
+        die: ["Your fireball hits $N with full force, causing an immediate death!",
+              "As you burst into flames of death $n grins evilly...",
+              "The heat from a fireball from $n turns $N into a charred corpse!"],
+        miss: ["Your fireball blooms few feet before $N who claps $S hands in wonder.",
+               "$n aims a fireball at you but it blooms few feet before you; you clap your hands at the show.",
+               "A fireball from $n misses $N -- what great fireworks around here!!"],
+        hit: ["You throw a fireball at $N and have the satisfaction of seeing $M enveloped in flames!",
+              "You are enveloped in flames from a fireball sent by $n -- OUCH!!",
+              "$n smirks as $s fireball explodes into the face of $N!"],
+    },
+    SkillMessages {
+        spell: SPELL_MAGIC_MISSILE,
+        die: ["The magic missile tears away the remaining life of $N!",
+              "You only have time to notice $n uttering strange sounds before everything is dark!",
+              "The magic missile sent by $n causes $N to stagger and collaps in a lifeless heap!"],
+        miss: ["The magic missile penetrates the air miles above $N.",
+               "You don't even have to duck as $n sends a magic missile way over your head.",
+               "$n tries in vain to correct the course of the magic missile meant to hit $N."],
+        hit: ["You watch with selfpride as your magic missile hits $N!",
+              "You stagger as a magic missile from $n hits you!",
+              "$n throws a magic missile at $N who staggers under the blow!"],
+    },
 ];
 
 pub struct Combat;
@@ -83,7 +162,7 @@ impl Combat {
         }
     }
     
-    pub fn perform_violence(attacker: Arc<RwLock<Character>>) -> (Vec<String>, Option<DeathResult>) {
+    pub fn perform_violence(attacker: Arc<RwLock<Character>>) -> (Vec<Act>, Option<DeathResult>) {
         let mut messages = Vec::new();
         let mut death: Option<DeathResult> = None;
 
@@ -108,13 +187,12 @@ impl Combat {
             };
 
             if can_attack {
-                let damage_msg = Combat::hit(attacker.clone(), victim.clone());
-                messages.push(damage_msg);
+                messages.extend(Combat::hit(attacker.clone(), victim.clone()));
 
                 // Check if victim died
                 let victim_dead = victim.read().points.hit <= 0;
                 if victim_dead {
-                    messages.push(Combat::death_cry(victim.clone()));
+                    messages.push(Combat::death_cry(&victim));
                     // Capture the victim's current room BEFORE we mutate
                     // anything (Combat::die clears fighting but not in_room).
                     let room_arc = victim.read().in_room.as_ref().and_then(|w| w.upgrade());
@@ -135,7 +213,7 @@ impl Combat {
         (messages, death)
     }
     
-    pub fn hit(attacker: Arc<RwLock<Character>>, victim: Arc<RwLock<Character>>) -> String {
+    pub fn hit(attacker: Arc<RwLock<Character>>, victim: Arc<RwLock<Character>>) -> Vec<Act> {
         let mut rng = rand::thread_rng();
 
         // Hit roll formula: CircleMUD stores AC as AC*10 so an unarmored
@@ -151,13 +229,25 @@ impl Combat {
         // Natural 1 is always a miss, natural 20 always a hit. Otherwise
         // the roll must meet or exceed `needed`.
         let miss = roll == 1 || (roll < 20 && roll < needed);
+        let w_type = Combat::attack_type(&attacker.read());
         if miss {
-            Combat::damage_message(&attacker.read(), &victim.read(), 0, DamageType::Hit)
+            Combat::dam_message(&attacker, &victim, 0, w_type)
         } else {
             let damage = Combat::calculate_damage(attacker.clone());
-            Combat::do_damage(attacker, victim, damage, DamageType::Hit)
+            Combat::do_damage(attacker, victim, damage, w_type)
         }
     }
+
+    /// The ATTACK_TYPES entry for `ch`'s swings: the wielded weapon's
+    /// fourth value, or a plain hit.
+    fn attack_type(ch: &Character) -> usize {
+        ch.equipment[WEAR_WIELD].as_ref()
+            .map(|weapon| weapon.read())
+            .filter(|obj| obj.is_weapon())
+            .and_then(|obj| usize::try_from(obj.values.value[3]).ok())
+            .filter(|&w_type| w_type < ATTACK_TYPES.len())
+            .unwrap_or(TYPE_HIT)
+    }
     
     fn calculate_thac0(ch: &Character) -> i16 {
         // THAC0 by class and level
@@ -214,73 +304,96 @@ impl Combat {
         }
     }
     
+    /// Weapon damage: apply it and describe it with dam_message.
     pub fn do_damage(
-        attacker: Arc<RwLock<Character>>, 
-        victim: Arc<RwLock<Character>>, 
+        attacker: Arc<RwLock<Character>>,
+        victim: Arc<RwLock<Character>>,
         damage: i32,
-        damage_type: DamageType
-    ) -> String {
-        // Apply damage
-        {
-            let mut vic = victim.write();
-            vic.points.hit -= damage;
-            vic.points.hit = vic.points.hit.max(-10);
-        }
-        
-        // Update position based on health
-        {
-            let mut vic = victim.write();
-            if vic.points.hit <= -10 {
-                vic.position = Position::Dead;
-            } else if vic.points.hit <= -3 {
-                vic.position = Position::MortalllyWounded;
-            } else if vic.points.hit <= 0 {
-                vic.position = Position::Incapacitated;
-            }
-        }
-        
-        Combat::damage_message(&attacker.read(), &victim.read(), damage, damage_type)
+        w_type: usize,
+    ) -> Vec<Act> {
+        Combat::apply_damage(&victim, damage);
+        Combat::dam_message(&attacker, &victim, damage, w_type)
     }
-    
-    fn damage_message(attacker: &Character, victim: &Character, damage: i32, _damage_type: DamageType) -> String {
-        let severity = match damage {
-            0 => "miss",
-            1..=3 => "scratch",
-            4..=6 => "bruise",
-            7..=10 => "hit",
-            11..=14 => "injure",
-            15..=19 => "wound",
-            20..=23 => "maul",
-            24..=27 => "decimate",
-            28..=31 => "devastate",
-            32..=35 => "maim",
-            36..=39 => "MUTILATE",
-            40..=43 => "DISEMBOWEL",
-            44..=47 => "DISMEMBER",
-            48..=52 => "MASSACRE",
-            53..=99 => "PULVERIZE",
-            _ => "*** ANNIHILATE ***",
-        };
 
-        // Render names. For NPCs, short_desc is "the goblin" / "a baker";
-        // for PCs it's just the name. CircleMUD's act() $n/$N placeholders
-        // do the same, but since we send one string to each viewer here,
-        // we resolve to attacker-first-person when the attacker is the
-        // reader. That nuance lives in game.rs — this function just emits
-        // a sentence in third person with proper names substituted.
-        let a = attacker.display_for_others();
-        let v = victim.display_for_others();
-        if damage == 0 {
-            format!("{} tries to hit {} but misses.", a, v)
+    /// Spell damage: apply it and describe it with the spell's death, miss
+    /// or hit messages from lib/misc/messages (skill_message).
+    pub fn skill_damage(
+        attacker: Arc<RwLock<Character>>,
+        victim: Arc<RwLock<Character>>,
+        damage: i32,
+        spell: i32,
+    ) -> Vec<Act> {
+        Combat::apply_damage(&victim, damage);
+        let Some(messages) = SKILL_MESSAGES.iter().find(|m| m.spell == spell) else {
+            return Combat::dam_message(&attacker, &victim, damage, TYPE_HIT);
+        };
+        let [to_char, to_victim, to_room] = if victim.read().position == Position::Dead {
+            messages.die
+        } else if damage == 0 {
+            messages.miss
         } else {
-            format!("{} {}s {}! [{}]", a, severity, v, damage)
+            messages.hit
+        };
+        vec![
+            Act::new(to_char, &attacker, TO_CHAR).vict(&victim),
+            Act::new(to_victim, &attacker, TO_VICT | TO_SLEEP).vict(&victim),
+            Act::new(to_room, &attacker, TO_NOTVICT).vict(&victim),
+        ]
+    }
+
+    fn apply_damage(victim: &Arc<RwLock<Character>>, damage: i32) {
+        let mut vic = victim.write();
+        vic.points.hit -= damage;
+        vic.points.hit = vic.points.hit.max(-10);
+
+        // Update position based on health
+        if vic.points.hit <= -10 {
+            vic.position = Position::Dead;
+        } else if vic.points.hit <= -3 {
+            vic.position = Position::MortalllyWounded;
+        } else if vic.points.hit <= 0 {
+            vic.position = Position::Incapacitated;
         }
     }
-    
-    fn death_cry(_victim: Arc<RwLock<Character>>) -> String {
-        "You hear someone's death cry.".to_string()
+
+    /// Describe a weapon blow to the room, the attacker and the victim.
+    /// DeltaMUD scaled the severities for its larger damage numbers; we
+    /// still deal stock CircleMUD damage, so this uses the stock ranges
+    /// noted against each entry of dam_weapons.
+    fn dam_message(
+        attacker: &Arc<RwLock<Character>>,
+        victim: &Arc<RwLock<Character>>,
+        damage: i32,
+        w_type: usize,
+    ) -> Vec<Act> {
+        let msgnum = match damage {
+            i32::MIN..=0 => 0,
+            1..=2 => 1,
+            3..=4 => 2,
+            5..=6 => 3,
+            7..=10 => 4,
+            11..=14 => 5,
+            15..=19 => 6,
+            20..=23 => 7,
+            24..=28 => 8,
+            29..=33 => 9,
+            34..=38 => 10,
+            _ => 11,
+        };
+        let attack = &ATTACK_TYPES[w_type];
+        let text = |msg: &str| msg.replace("#w", attack.singular).replace("#W", attack.plural);
+        let [to_room, to_char, to_victim] = DAM_WEAPONS[msgnum];
+        vec![
+            Act::new(text(to_room), attacker, TO_NOTVICT).vict(victim),
+            Act::new(text(to_char), attacker, TO_CHAR).vict(victim),
+            Act::new(text(to_victim), attacker, TO_VICT | TO_SLEEP).vict(victim),
+        ]
     }
-    
+
+    fn death_cry(victim: &Arc<RwLock<Character>>) -> Act {
+        Act::new("Your blood freezes as you hear $n's death cry.", victim, TO_ROOM)
+    }
+
     /// Clear the victim's fighting state so no one else in-round continues
     /// targeting them. The heavier work — corpse creation, extracting NPCs,
     /// respawning PCs — runs in Game::handle_death (which holds the World
@@ -1,12 +1,84 @@
+use crate::act::{self, Act, TO_CHAR, TO_ROOM, TO_SLEEP};
 use crate::character::Character;
 use crate::world::World;
 use crate::types::*;
-use crate::combat::Combat;
-use crate::magic::{SPELL_INFO, can_cast};
-use crate::object::Object;
+use crate::combat::{Combat, WAIT_ROUND};
+use crate::interpreter::{
+    find_all_dots, isname, two_arguments, FindDots, SCMD_CLOSE, SCMD_LOCK, SCMD_OPEN, SCMD_PICK, SCMD_UNLOCK,
+};
+use crate::magic::{AFF_DETECT_EVIL, AFF_DETECT_MAGIC, SPELL_INFO, SKILL_PICK_LOCK, can_cast, get_skill};
+use crate::object::{
+    ExtraFlags, Object, ObjectType, CONT_CLOSEABLE, CONT_CLOSED, CONT_LOCKED, CONT_PICKPROOF,
+};
+use crate::room::{Exit, Room, EX_CLOSED, EX_LOCKED};
+use rand::Rng;
 use std::sync::Arc;
 use parking_lot::RwLock;
 
+/// Where find_obj found an object (the FIND_OBJ_x bits of generic_find)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Found {
+    Inv,
+    Room,
+    Equip,
+}
+
+/// What do_gen_door needs to know about a door or a container.
+struct LockState {
+    openable: bool,
+    closed: bool,
+    locked: bool,
+    pickproof: bool,
+    key: ObjVnum,
+}
+
+impl LockState {
+    fn of_exit(exit: &Exit) -> Self {
+        LockState {
+            openable: exit.is_door(),
+            closed: exit.is_closed(),
+            locked: exit.is_locked(),
+            pickproof: exit.is_pickproof(),
+            key: exit.key,
+        }
+    }
+
+    fn of_obj(obj: &Object) -> Self {
+        LockState {
+            openable: obj.cont_flagged(CONT_CLOSEABLE),
+            closed: obj.cont_flagged(CONT_CLOSED),
+            locked: obj.cont_flagged(CONT_LOCKED),
+            pickproof: obj.cont_flagged(CONT_PICKPROOF),
+            key: obj.values.value[2],
+        }
+    }
+}
+
+/// Liquid colors, by liquid type (color_liquid in /web/deltamud/src/constants.c)
+const COLOR_LIQUID: [&str; 16] = [
+    "clear", "brown", "clear", "brown", "dark", "golden", "red", "green",
+    "clear", "light green", "white", "brown", "black", "red", "clear", "crystal clear",
+];
+
+/// How full a drink container is, by thirds (fullness)
+const FULLNESS: [&str; 4] = ["less than half ", "about half ", "more than half ", ""];
+
+/// Command words for do_gen_door, by subcommand (cmd_door)
+const CMD_DOOR: [&str; 5] = ["open", "close", "unlock", "lock", "pick"];
+
+// What state a door has to be in for each do_gen_door subcommand (flags_door)
+const NEED_OPEN: u8 = 1;
+const NEED_CLOSED: u8 = 2;
+const NEED_UNLOCKED: u8 = 4;
+const NEED_LOCKED: u8 = 8;
+const FLAGS_DOOR: [u8; 5] = [
+    NEED_CLOSED | NEED_UNLOCKED,
+    NEED_OPEN,
+    NEED_CLOSED | NEED_LOCKED,
+    NEED_CLOSED | NEED_UNLOCKED,
+    NEED_CLOSED | NEED_LOCKED,
+];
+
 pub struct Commands;
 
 impl Commands {
@@ -114,37 +186,438 @@ impl Commands {
     }
     
     // Object manipulation
-    pub fn do_get(ch: &mut Character, _world: &World, args: &str) -> Vec<String> {
-        let mut messages = Vec::new();
-        
-        if args.is_empty() {
-            messages.push("Get what?".to_string());
-            return messages;
+    /// get and take (do_get in /web/deltamud/src/act.item.c): from the
+    /// ground, or out of a container carried or on the ground, corpses
+    /// included. Either argument may be "all" or "all.x".
+    pub fn do_get(ch: Arc<RwLock<Character>>, _world: &World, args: &str) -> Vec<Act> {
+        let mut acts = Vec::new();
+        let (arg1, arg2) = two_arguments(args);
+        let room = match ch.read().in_room.as_ref().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return acts,
+        };
+        if arg1.is_empty() {
+            acts.push(Act::new("Get what?", &ch, TO_CHAR));
+            return acts;
         }
-        
-        // Find object in room
-        if let Some(room_weak) = &ch.in_room {
-            if let Some(room) = room_weak.upgrade() {
-                let mut room = room.write();
-                
-                // Simple name matching (should be improved)
-                let obj_index = room.contents.iter().position(|obj| {
-                    obj.read().name.to_lowercase().contains(&args.to_lowercase())
-                });
-                
-                if let Some(index) = obj_index {
-                    let obj = room.contents.remove(index);
-                    messages.push(format!("You get {}.", obj.read().short_description));
-                    ch.carrying.push(obj);
-                } else {
-                    messages.push("You don't see that here.".to_string());
+        if arg2.is_empty() {
+            Commands::get_from_room(&ch, &room, arg1, &mut acts);
+            return acts;
+        }
+
+        let (cont_dots, cont_name) = find_all_dots(arg2);
+        if cont_dots == FindDots::Indiv {
+            let cont = Commands::find_obj(&ch.read(), &room.read(), cont_name, false);
+            match cont {
+                None => acts.push(Act::new(format!("You don't have {} {}.", act::an(arg2), act::escape(arg2)), &ch, TO_CHAR)),
+                Some((cont, _)) if !cont.read().is_container() => {
+                    acts.push(Act::new("$p is not a container.", &ch, TO_CHAR).obj(&cont));
                 }
+                Some((cont, found)) => Commands::get_from_container(&ch, &cont, arg1, found, &mut acts),
             }
+            return acts;
+        }
+        if cont_dots == FindDots::AllDot && cont_name.is_empty() {
+            acts.push(Act::new("Get from all of what?", &ch, TO_CHAR));
+            return acts;
+        }
+
+        // Containers carried first, then those on the ground
+        let conts: Vec<(Arc<RwLock<Object>>, Found)> = {
+            let ch = ch.read();
+            let room = room.read();
+            ch.carrying.iter().map(|o| (o.clone(), Found::Inv))
+                .chain(room.contents.iter().map(|o| (o.clone(), Found::Room)))
+                .filter(|(o, _)| {
+                    let o = o.read();
+                    act::can_see_obj(&ch, &o) && (cont_dots == FindDots::All || isname(cont_name, &o.name))
+                })
+                .collect()
+        };
+        let mut found = false;
+        for (cont, place) in conts {
+            if cont.read().is_container() {
+                found = true;
+                Commands::get_from_container(&ch, &cont, arg1, place, &mut acts);
+            } else if cont_dots == FindDots::AllDot {
+                found = true;
+                acts.push(Act::new("$p is not a container.", &ch, TO_CHAR).obj(&cont));
+            }
+        }
+        if !found {
+            let msg = match cont_dots {
+                FindDots::All => "You can't seem to find any containers.".to_string(),
+                _ => format!("You can't seem to find any {}s here.", act::escape(cont_name)),
+            };
+            acts.push(Act::new(msg, &ch, TO_CHAR));
+        }
+        acts
+    }
+
+    /// get with no container (get_from_room).
+    fn get_from_room(ch: &Arc<RwLock<Character>>, room: &Arc<RwLock<Room>>, arg: &str, acts: &mut Vec<Act>) {
+        let (dots, name) = find_all_dots(arg);
+        if dots == FindDots::Indiv {
+            let obj = Commands::obj_in_list(&ch.read(), name, &room.read().contents);
+            match obj {
+                Some(obj) => Commands::perform_get_from_room(ch, room, &obj, acts),
+                None => acts.push(Act::new(format!("You don't see {} {} here.", act::an(name), act::escape(name)), ch, TO_CHAR)),
+            }
+            return;
+        }
+        if dots == FindDots::AllDot && name.is_empty() {
+            acts.push(Act::new("Get all of what?", ch, TO_CHAR));
+            return;
+        }
+        let objs = Commands::objs_in_list(&ch.read(), dots, name, &room.read().contents);
+        if objs.is_empty() {
+            let msg = match dots {
+                FindDots::All => "There doesn't seem to be anything here.".to_string(),
+                _ => format!("You don't see any {}s here.", act::escape(name)),
+            };
+            acts.push(Act::new(msg, ch, TO_CHAR));
+        }
+        for obj in objs {
+            Commands::perform_get_from_room(ch, room, &obj, acts);
+        }
+    }
+
+    fn perform_get_from_room(
+        ch: &Arc<RwLock<Character>>,
+        room: &Arc<RwLock<Room>>,
+        obj: &Arc<RwLock<Object>>,
+        acts: &mut Vec<Act>,
+    ) {
+        if !Commands::can_take_obj(&ch.read(), &obj.read()) {
+            acts.push(Act::new("$p: you can't take that!", ch, TO_CHAR).obj(obj));
+            return;
+        }
+        room.write().contents.retain(|o| !Arc::ptr_eq(o, obj));
+        {
+            let mut o = obj.write();
+            o.in_room = None;
+            o.carried_by = Some(Arc::downgrade(ch));
+        }
+        ch.write().carrying.push(obj.clone());
+        acts.push(Act::new("You get $p.", ch, TO_CHAR).obj(obj));
+        acts.push(Act::new("$n gets $p.", ch, TO_ROOM).obj(obj).hide_invisible());
+    }
+
+    /// get out of one container (get_from_container). `place` is where
+    /// the container is; one on the ground only gives up what may be
+    /// taken.
+    fn get_from_container(
+        ch: &Arc<RwLock<Character>>,
+        cont: &Arc<RwLock<Object>>,
+        arg: &str,
+        place: Found,
+        acts: &mut Vec<Act>,
+    ) {
+        if cont.read().cont_flagged(CONT_CLOSED) {
+            acts.push(Act::new("$p is closed.", ch, TO_CHAR).obj(cont));
+            return;
+        }
+        let (dots, name) = find_all_dots(arg);
+        let quoted = act::escape(name);
+        if dots == FindDots::Indiv {
+            let obj = Commands::obj_in_list(&ch.read(), name, &cont.read().contains);
+            match obj {
+                Some(obj) => Commands::perform_get_from_container(ch, &obj, cont, place, acts),
+                None => {
+                    let msg = format!("There doesn't seem to be {} {} in $p.", act::an(name), quoted);
+                    acts.push(Act::new(msg, ch, TO_CHAR).obj(cont));
+                }
+            }
+            return;
+        }
+        if dots == FindDots::AllDot && name.is_empty() {
+            acts.push(Act::new("Get all of what?", ch, TO_CHAR));
+            return;
+        }
+        let objs = Commands::objs_in_list(&ch.read(), dots, name, &cont.read().contains);
+        if objs.is_empty() {
+            let msg = match dots {
+                FindDots::All => "$p seems to be empty.".to_string(),
+                _ => format!("You can't seem to find any {}s in $p.", quoted),
+            };
+            acts.push(Act::new(msg, ch, TO_CHAR).obj(cont));
+        }
+        for obj in objs {
+            Commands::perform_get_from_container(ch, &obj, cont, place, acts);
+        }
+    }
+
+    fn perform_get_from_container(
+        ch: &Arc<RwLock<Character>>,
+        obj: &Arc<RwLock<Object>>,
+        cont: &Arc<RwLock<Object>>,
+        place: Found,
+        acts: &mut Vec<Act>,
+    ) {
+        if place != Found::Inv && !Commands::can_take_obj(&ch.read(), &obj.read()) {
+            acts.push(Act::new("$p: you can't take that!", ch, TO_CHAR).obj(obj));
+            return;
+        }
+        cont.write().contains.retain(|o| !Arc::ptr_eq(o, obj));
+        {
+            let mut o = obj.write();
+            o.in_obj = None;
+            o.carried_by = Some(Arc::downgrade(ch));
+        }
+        ch.write().carrying.push(obj.clone());
+        acts.push(Act::new("You get $p from $P.", ch, TO_CHAR).obj(obj).vict_obj(cont));
+        acts.push(Act::new("$n gets $p from $P.", ch, TO_ROOM).obj(obj).vict_obj(cont).hide_invisible());
+    }
+
+    /// Whether `ch` may pick `obj` up (can_take_obj). Carrying limits
+    /// aren't kept yet, so this only stops mortals taking what has no
+    /// TAKE flag.
+    fn can_take_obj(ch: &Character, obj: &Object) -> bool {
+        ch.is_immortal() || obj.can_wear(crate::object::WearFlags::TAKE)
+    }
+
+    /// put <obj | all | all.x> <container> (do_put). The container may
+    /// be carried or on the ground; what goes in must be carried.
+    pub fn do_put(ch: Arc<RwLock<Character>>, _world: &World, args: &str) -> Vec<Act> {
+        let to_char = |msg: String| vec![Act::new(msg, &ch, TO_CHAR)];
+        let (arg1, arg2) = two_arguments(args);
+        let (obj_dots, obj_name) = find_all_dots(arg1);
+        let (cont_dots, _) = find_all_dots(arg2);
+
+        if arg1.is_empty() {
+            return to_char("Put what in what?".to_string());
+        } else if cont_dots != FindDots::Indiv {
+            return to_char("You can only put things into one container at a time.".to_string());
+        } else if arg2.is_empty() {
+            let what = if obj_dots == FindDots::Indiv { "it" } else { "them" };
+            return to_char(format!("What do you want to put {} in?", what));
+        }
+
+        let room = match ch.read().in_room.as_ref().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return Vec::new(),
+        };
+        let cont = match Commands::find_obj(&ch.read(), &room.read(), arg2, false) {
+            Some((cont, _)) => cont,
+            None => return to_char(format!("You don't see {} {} here.", act::an(arg2), act::escape(arg2))),
+        };
+        if !cont.read().is_container() {
+            return vec![Act::new("$p is not a container.", &ch, TO_CHAR).obj(&cont)];
+        }
+        if cont.read().cont_flagged(CONT_CLOSED) {
+            return to_char("You'd better open it first!".to_string());
+        }
+
+        let mut acts = Vec::new();
+        if obj_dots == FindDots::Indiv {
+            let obj = Commands::obj_in_list(&ch.read(), obj_name, &ch.read().carrying);
+            match obj {
+                None => return to_char(format!("You aren't carrying {} {}.", act::an(obj_name), act::escape(obj_name))),
+                Some(obj) if Arc::ptr_eq(&obj, &cont) => {
+                    return to_char("You attempt to fold it into itself, but fail.".to_string());
+                }
+                Some(obj) => Commands::perform_put(&ch, &obj, &cont, &mut acts),
+            }
+            return acts;
+        }
+
+        let objs: Vec<_> = Commands::objs_in_list(&ch.read(), obj_dots, obj_name, &ch.read().carrying)
+            .into_iter()
+            .filter(|obj| !Arc::ptr_eq(obj, &cont))
+            .collect();
+        if objs.is_empty() {
+            return to_char(match obj_dots {
+                FindDots::All => "You don't seem to have anything to put in it.".to_string(),
+                _ => format!("You don't seem to have any {}s.", act::escape(obj_name)),
+            });
+        }
+        for obj in objs {
+            Commands::perform_put(&ch, &obj, &cont, &mut acts);
+        }
+        acts
+    }
+
+    /// Put one carried object into a container, if it fits (perform_put).
+    /// Weights count everything inside, so a full bag in a chest takes
+    /// up its whole weight.
+    fn perform_put(ch: &Arc<RwLock<Character>>, obj: &Arc<RwLock<Object>>, cont: &Arc<RwLock<Object>>, acts: &mut Vec<Act>) {
+        let weight = cont.read().get_total_weight() + obj.read().get_total_weight();
+        if weight > cont.read().capacity() {
+            acts.push(Act::new("$p won't fit in $P.", ch, TO_CHAR).obj(obj).vict_obj(cont));
+            return;
+        }
+        ch.write().carrying.retain(|o| !Arc::ptr_eq(o, obj));
+        {
+            let mut o = obj.write();
+            o.carried_by = None;
+            o.in_obj = Some(Arc::downgrade(cont));
+        }
+        cont.write().add_to_container(obj.clone());
+        acts.push(Act::new("You put $p in $P.", ch, TO_CHAR).obj(obj).vict_obj(cont));
+        acts.push(Act::new("$n puts $p in $P.", ch, TO_ROOM).obj(obj).vict_obj(cont).hide_invisible());
+    }
+
+    /// look in <container> (look_in_obj in /web/deltamud/src/act.informative.c).
+    pub fn look_in_obj(ch: &Character, _world: &World, arg: &str) -> Vec<String> {
+        if arg.is_empty() {
+            return vec!["Look in what?".to_string()];
+        }
+        let room = match ch.in_room.as_ref().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return Vec::new(),
+        };
+        let (obj, place) = match Commands::find_obj(ch, &room.read(), arg, true) {
+            Some(found) => found,
+            None => return vec![format!("There doesn't seem to be {} {} here.", act::an(arg), arg)],
+        };
+        let obj = obj.read();
+
+        match obj.obj_type {
+            ObjectType::Container if obj.cont_flagged(CONT_CLOSED) => vec!["It is closed.".to_string()],
+            ObjectType::Container => {
+                let place = match place {
+                    Found::Inv => "carried",
+                    Found::Room => "here",
+                    Found::Equip => "used",
+                };
+                let mut messages = vec![format!("{} ({}): ", obj.fname(), place)];
+                messages.extend(Commands::list_obj_to_char(ch, &obj.contains));
+                messages
+            }
+            ObjectType::LiqContainer | ObjectType::Fountain => {
+                let [capacity, contains, liquid, _] = obj.values.value;
+                let msg = if contains <= 0 {
+                    "It is empty.".to_string()
+                } else if capacity <= 0 || contains > capacity {
+                    "Its contents seem somewhat murky.".to_string()
+                } else {
+                    let color = usize::try_from(liquid).ok()
+                        .and_then(|l| COLOR_LIQUID.get(l))
+                        .unwrap_or(&"UNDEFINED");
+                    format!("It's {}full of a {} liquid.", FULLNESS[(contains * 3 / capacity) as usize], color)
+                };
+                vec![msg]
+            }
+            _ => vec!["There's nothing inside that!".to_string()],
+        }
+    }
+
+    /// examine <object>: how it looks, and what's inside if it holds
+    /// anything (do_examine).
+    pub fn do_examine(ch: &Character, world: &World, args: &str) -> Vec<String> {
+        let (arg, _) = two_arguments(args);
+        if arg.is_empty() {
+            return vec!["Examine what?".to_string()];
+        }
+        let room = match ch.in_room.as_ref().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return Vec::new(),
+        };
+        let obj = match Commands::find_obj(ch, &room.read(), arg, true) {
+            Some((obj, _)) => obj,
+            None => return vec!["You do not see that here.".to_string()],
+        };
+
+        let (mut messages, holds) = {
+            let obj = obj.read();
+            let look = match obj.obj_type {
+                ObjectType::Note => match &obj.action_description {
+                    Some(text) => format!("There is something written upon it:\r\n\r\n{}", text),
+                    None => "It's blank.".to_string(),
+                },
+                ObjectType::LiqContainer => "It looks like a drink container.".to_string(),
+                _ => format!("You see nothing special..{}", Commands::obj_flags_seen(ch, &obj)),
+            };
+            let holds = matches!(obj.obj_type, ObjectType::Container | ObjectType::LiqContainer | ObjectType::Fountain);
+            (vec![look], holds)
+        };
+        if holds {
+            messages.push("When you look inside, you see:".to_string());
+            messages.extend(Commands::look_in_obj(ch, world, arg));
         }
-        
         messages
     }
-    
+
+    /// One line per kind of object, with a count where there's more than
+    /// one alike (list_obj_to_char).
+    fn list_obj_to_char(ch: &Character, list: &[Arc<RwLock<Object>>]) -> Vec<String> {
+        let mut lines: Vec<(String, usize)> = Vec::new();
+        let mut kinds: Vec<(ObjVnum, String)> = Vec::new();
+        for obj in list {
+            let obj = obj.read();
+            if !act::can_see_obj(ch, &obj) {
+                continue;
+            }
+            // Objects without a prototype are alike if they read alike
+            let kind = (obj.item_number, if obj.item_number == NOTHING { obj.short_description.clone() } else { String::new() });
+            match kinds.iter().position(|k| *k == kind) {
+                Some(i) => lines[i].1 += 1,
+                None => {
+                    kinds.push(kind);
+                    lines.push((format!("{}{}", obj.short_description, Commands::obj_flags_seen(ch, &obj)), 1));
+                }
+            }
+        }
+        if lines.is_empty() {
+            return vec![" Nothing.".to_string()];
+        }
+        lines.into_iter()
+            .map(|(line, n)| if n == 1 { line } else { format!("({}) {}", n, line) })
+            .collect()
+    }
+
+    /// The notes show_obj_to_char adds after an object's description.
+    fn obj_flags_seen(ch: &Character, obj: &Object) -> String {
+        let mut notes = String::new();
+        if obj.extra_flags.contains(ExtraFlags::INVISIBLE) {
+            notes.push_str(" (invisible)");
+        }
+        if obj.extra_flags.contains(ExtraFlags::BLESS) && ch.affect_flags & AFF_DETECT_EVIL != 0 {
+            notes.push_str(" ..It glows blue!");
+        }
+        if obj.extra_flags.contains(ExtraFlags::MAGIC) && ch.affect_flags & AFF_DETECT_MAGIC != 0 {
+            notes.push_str(" ..It glows yellow!");
+        }
+        if obj.extra_flags.contains(ExtraFlags::GLOW) {
+            notes.push_str(" ..It has a soft glowing aura!");
+        }
+        if obj.extra_flags.contains(ExtraFlags::HUM) {
+            notes.push_str(" ..It emits a faint humming sound!");
+        }
+        notes
+    }
+
+    /// The first object in `list` called `name` that `ch` can see
+    /// (get_obj_in_list_vis).
+    fn obj_in_list(ch: &Character, name: &str, list: &[Arc<RwLock<Object>>]) -> Option<Arc<RwLock<Object>>> {
+        list.iter()
+            .find(|obj| {
+                let obj = obj.read();
+                isname(name, &obj.name) && act::can_see_obj(ch, &obj)
+            })
+            .cloned()
+    }
+
+    /// Everything in `list` that "all" or "all.name" picks out.
+    fn objs_in_list(ch: &Character, dots: FindDots, name: &str, list: &[Arc<RwLock<Object>>]) -> Vec<Arc<RwLock<Object>>> {
+        list.iter()
+            .filter(|obj| {
+                let obj = obj.read();
+                act::can_see_obj(ch, &obj) && (dots == FindDots::All || isname(name, &obj.name))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The object called `name`: worn, if `equip`, then carried, then on
+    /// the ground (generic_find).
+    fn find_obj(ch: &Character, room: &Room, name: &str, equip: bool) -> Option<(Arc<RwLock<Object>>, Found)> {
+        let worn: Vec<_> = if equip { ch.equipment.iter().flatten().cloned().collect() } else { Vec::new() };
+        Commands::obj_in_list(ch, name, &worn).map(|obj| (obj, Found::Equip))
+            .or_else(|| Commands::obj_in_list(ch, name, &ch.carrying).map(|obj| (obj, Found::Inv)))
+            .or_else(|| Commands::obj_in_list(ch, name, &room.contents).map(|obj| (obj, Found::Room)))
+    }
+
     pub fn do_drop(ch: &mut Character, _world: &World, args: &str) -> Vec<String> {
         let mut messages = Vec::new();
         
@@ -280,6 +753,7 @@ impl Commands {
                             drop(room);
                             
                             Combat::start_fighting(ch.clone(), person.clone());
+                            ch.write().wait_state(WAIT_ROUND + 2);
                             messages.push("You attack!".to_string());
                             // The actual swing runs next PULSE_VIOLENCE tick
                             // via Game::process_combat, which also handles
@@ -296,12 +770,13 @@ impl Commands {
     }
     
     // Magic commands
-    pub fn do_cast(ch: Arc<RwLock<Character>>, _world: &World, args: &str) -> Vec<String> {
+    pub fn do_cast(ch: Arc<RwLock<Character>>, _world: &World, args: &str) -> Vec<Act> {
         let mut messages = Vec::new();
+        let to_char = |msg: &str| Act::new(msg, &ch, TO_CHAR);
         let parts: Vec<&str> = args.split_whitespace().collect();
         
         if parts.is_empty() {
-            messages.push("Cast which spell?".to_string());
+            messages.push(to_char("Cast which spell?"));
             return messages;
         }
         
@@ -318,7 +793,7 @@ impl Commands {
             
             // Check if can cast
             if let Err(msg) = can_cast(&ch_read, spell_num) {
-                messages.push(msg);
+                messages.push(to_char(&msg));
                 return messages;
             }
             
@@ -332,7 +807,7 @@ impl Commands {
                 } else if spell_info.targets.contains(crate::magic::TargetFlags::TAR_SELF_ONLY) {
                     Some(ch.clone())
                 } else {
-                    messages.push("Cast on whom?".to_string());
+                    messages.push(to_char("Cast on whom?"));
                     return messages;
                 }
             } else {
@@ -347,18 +822,169 @@ impl Commands {
             // Cast spell
             let level = ch.read().player.level;
             let result = (spell_info.routine)(level, ch.clone(), target);
+            ch.write().wait_state(WAIT_ROUND);
             
-            messages.push(format!("You cast {}.", spell_info.name));
-            if !result.is_empty() {
-                messages.push(result);
-            }
+            messages.push(to_char(&format!("You cast {}.", spell_info.name)));
+            messages.extend(result);
         } else {
-            messages.push("You don't know that spell!".to_string());
+            messages.push(to_char("You don't know that spell!"));
         }
         
         messages
     }
     
+    // Doors
+    /// open, close, unlock, lock and pick (do_gen_door in
+    /// /web/deltamud/src/act.movement.c), on a container in hand or on
+    /// the ground, or else a door. A door changes on both sides, and
+    /// anyone on the far side hears it open or close.
+    pub fn do_gen_door(ch: Arc<RwLock<Character>>, world: &World, args: &str, subcmd: i32) -> Vec<Act> {
+        let to_char = |msg: &str| vec![Act::new(msg, &ch, TO_CHAR)];
+        let cmd = CMD_DOOR[subcmd as usize];
+        let (kind, dir) = two_arguments(args);
+        if kind.is_empty() {
+            return to_char(&format!("{}{} what?", cmd[..1].to_uppercase(), &cmd[1..]));
+        }
+
+        let room = match ch.read().in_room.as_ref().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return Vec::new(),
+        };
+        let obj = Commands::find_obj(&ch.read(), &room.read(), kind, false);
+        let (lock, door) = match &obj {
+            Some((obj, _)) => (LockState::of_obj(&obj.read()), None),
+            None => {
+                let room = room.read();
+                match Commands::find_door(&room, kind, dir) {
+                    Ok((door, exit)) => (LockState::of_exit(exit), Some((room.number, door, exit.clone()))),
+                    Err(msg) => return to_char(&msg),
+                }
+            }
+        };
+
+        let needs = FLAGS_DOOR[subcmd as usize];
+        let refusal = {
+            let ch = ch.read();
+            if !lock.openable {
+                Some(format!("You can't {} that!", cmd))
+            } else if lock.closed && needs & NEED_OPEN != 0 {
+                Some("But it's already closed!".to_string())
+            } else if !lock.closed && needs & NEED_CLOSED != 0 {
+                Some("But it's currently open!".to_string())
+            } else if !lock.locked && needs & NEED_LOCKED != 0 {
+                Some("Oh.. it wasn't locked, after all..".to_string())
+            } else if lock.locked && needs & NEED_UNLOCKED != 0 {
+                Some("It seems to be locked.".to_string())
+            } else if (subcmd == SCMD_LOCK || subcmd == SCMD_UNLOCK)
+                && !Commands::has_key(&ch, lock.key) && ch.player.level < LVL_GOD {
+                Some("You don't seem to have the proper key.".to_string())
+            } else if subcmd == SCMD_PICK {
+                Commands::ok_pick(&ch, &lock).err().map(str::to_string)
+            } else {
+                None
+            }
+        };
+        if let Some(msg) = refusal {
+            return to_char(&msg);
+        }
+
+        let (closed, locked) = if obj.is_some() { (CONT_CLOSED, CONT_LOCKED) } else { (EX_CLOSED, EX_LOCKED) };
+        let update = |flags: &mut i32| match subcmd {
+            SCMD_OPEN => *flags &= !closed,
+            SCMD_CLOSE => *flags |= closed,
+            SCMD_LOCK => *flags |= locked,
+            _ => *flags &= !locked,
+        };
+        let done = match subcmd {
+            SCMD_OPEN | SCMD_CLOSE => "Ok.",
+            SCMD_PICK => "The lock quickly yields to your skills.",
+            _ => "*Click*",
+        };
+        let to_room = if subcmd == SCMD_PICK {
+            "$n skillfully picks the lock on ".to_string()
+        } else {
+            format!("$n {}s ", cmd)
+        };
+        let mut acts = to_char(done);
+
+        let (room_vnum, door, exit) = match (obj, door) {
+            (Some((obj, found)), _) => {
+                update(&mut obj.write().values.value[1]);
+                // Nobody sees what's done to a container in someone's pack
+                if found == Found::Room {
+                    acts.push(Act::new(format!("{}$p.", to_room), &ch, TO_ROOM).obj(&obj));
+                }
+                return acts;
+            }
+            (None, Some(door)) => door,
+            (None, None) => return acts,
+        };
+        world.update_door(room_vnum, door, update);
+        acts.push(Act::new(format!("{}the $T.", to_room), &ch, TO_ROOM).text(exit.door_name()));
+
+        // Tell the other side, if the door leads straight back
+        if subcmd == SCMD_OPEN || subcmd == SCMD_CLOSE {
+            if let Some(other) = world.get_room(exit.to_room) {
+                let other = other.read();
+                if let Some(back) = other.get_exit(REV_DIR[door]).filter(|b| b.to_room == room_vnum) {
+                    // send_to_room in C, so sleepers hear it too
+                    let msg = format!("The $T is {} from the other side.",
+                        if subcmd == SCMD_OPEN { "opened" } else { "closed" });
+                    acts.extend(other.people.iter()
+                        .filter_map(|p| p.upgrade())
+                        .filter(|p| !Arc::ptr_eq(p, &ch))
+                        .map(|p| Act::new(msg.clone(), &p, TO_CHAR | TO_SLEEP).text(back.door_name())));
+                }
+            }
+        }
+        acts
+    }
+
+    /// The exit called `kind`, or the one in direction `dir` if that's
+    /// given (find_door).
+    fn find_door<'a>(room: &'a Room, kind: &str, dir: &str) -> Result<(usize, &'a Exit), String> {
+        if !dir.is_empty() {
+            let dir = dir.to_lowercase();
+            let door = DIRS.iter().position(|d| d.starts_with(&dir))
+                .ok_or_else(|| "That's not a direction.".to_string())?;
+            return match room.get_exit(door) {
+                Some(exit) => match &exit.keyword {
+                    Some(keyword) if !isname(kind, keyword) => Err(format!("I see no {} there.", act::escape(kind))),
+                    _ => Ok((door, exit)),
+                },
+                None => Err("I really don't see how you can close anything there.".to_string()),
+            };
+        }
+        room.exits.iter().enumerate()
+            .find_map(|(door, exit)| {
+                exit.as_ref()
+                    .filter(|e| e.keyword.as_deref().is_some_and(|k| isname(kind, k)))
+                    .map(|e| (door, e))
+            })
+            .ok_or_else(|| format!("There doesn't seem to be {} {} here.", act::an(kind), act::escape(kind)))
+    }
+
+    /// Whether `ch` carries or holds the key with vnum `key` (has_key).
+    fn has_key(ch: &Character, key: ObjVnum) -> bool {
+        key >= 0 && ch.carrying.iter()
+            .chain(ch.equipment[WEAR_HOLD].iter())
+            .any(|obj| obj.read().item_number == key)
+    }
+
+    /// Roll to pick `lock` (ok_pick).
+    fn ok_pick(ch: &Character, lock: &LockState) -> Result<(), &'static str> {
+        let percent = rand::thread_rng().gen_range(1..=101);
+        if lock.key < 0 {
+            Err("Odd - you can't seem to find a keyhole.")
+        } else if lock.pickproof {
+            Err("It resists your attempts to pick it.")
+        } else if percent > get_skill(ch, SKILL_PICK_LOCK) {
+            Err("You failed to pick the lock.")
+        } else {
+            Ok(())
+        }
+    }
+
     // Utility functions
     fn find_eq_pos(ch: &Character, obj: &Object) -> Option<usize> {
         use crate::object::WearFlags;
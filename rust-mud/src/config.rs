@@ -1,11 +1,32 @@
 use std::env;
 
+#[derive(Clone)]
 pub struct Config {
     pub database_url: String,
     pub lib_path: String,
     pub port: u16,
+    pub websocket_port: Option<u16>,
+    pub tls_port: Option<u16>,
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
     pub use_compat_mode: bool,
     pub use_mock_db: bool,
+    /// Expect a PROXY protocol header from a load balancer on every
+    /// connection.
+    pub proxy_protocol: bool,
+    /// Seconds a link-dead character stays in the world before being
+    /// saved and extracted.
+    pub linkdead_timeout: u64,
+    /// Seconds of idling before a player is pulled into the void.
+    pub idle_void_timeout: u64,
+    /// Seconds of idling before a player is saved and disconnected.
+    pub idle_rent_timeout: u64,
+    /// Seconds a connection may sit at the name or password prompt.
+    pub login_timeout: u64,
+    /// Connections one address may have open at once (0 for no limit).
+    pub max_conns_per_ip: usize,
+    /// New connections one address may open per minute (0 for no limit).
+    pub max_conns_per_minute: usize,
 }
 
 impl Config {
@@ -19,6 +40,14 @@ impl Config {
                 .unwrap_or_else(|_| "4000".to_string())
                 .parse()
                 .unwrap_or(4000),
+            websocket_port: env::var("MUD_WS_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok()),
+            tls_port: env::var("MUD_TLS_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok()),
+            tls_cert_path: env::var("MUD_TLS_CERT").ok(),
+            tls_key_path: env::var("MUD_TLS_KEY").ok(),
             use_compat_mode: env::var("MUD_COMPAT_MODE")
                 .unwrap_or_else(|_| "false".to_string())
                 .parse()
@@ -27,6 +56,34 @@ impl Config {
                 .unwrap_or_else(|_| "false".to_string())
                 .parse()
                 .unwrap_or(false),
+            proxy_protocol: env::var("MUD_PROXY_PROTOCOL")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            linkdead_timeout: env::var("MUD_LINKDEAD_TIMEOUT")
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(300),
+            idle_void_timeout: env::var("MUD_IDLE_VOID_TIMEOUT")
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(600),
+            idle_rent_timeout: env::var("MUD_IDLE_RENT_TIMEOUT")
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(3600),
+            login_timeout: env::var("MUD_LOGIN_TIMEOUT")
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(60),
+            max_conns_per_ip: env::var("MUD_MAX_CONNS_PER_IP")
+                .ok()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(5),
+            max_conns_per_minute: env::var("MUD_MAX_CONNS_PER_MINUTE")
+                .ok()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(10),
         }
     }
 }
\ No newline at end of file
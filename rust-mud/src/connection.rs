@@ -1,11 +1,63 @@
-use tokio::net::TcpStream;
-use tokio::io::{AsyncWriteExt, BufReader, AsyncBufReadExt};
-use tokio::sync::mpsc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{timeout, Duration, Instant};
 use std::sync::Arc;
 use parking_lot::RwLock;
 use crate::character::Character;
+use crate::copyover::SavedConnection;
+use crate::gmcp;
+use crate::msdp::{self, MsdpValue};
+use crate::hedit::Hedit;
+use crate::pager::Pager;
+use crate::history::History;
+use crate::types::RoomVnum;
+use crate::telnet::{
+    self, ColorLevel, OptionSide, TelnetEvent, TelnetOptions, TelnetParser, TerminalInfo,
+    TELOPT_COMPRESS2, TELOPT_ECHO, TELOPT_GMCP, TELOPT_MSDP, TELOPT_NAWS, TELOPT_TTYPE, TTYPE_SEND,
+};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
 use std::net::SocketAddr;
+use std::os::fd::RawFd;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use anyhow::Result;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use log::{debug, warn};
+use serde_json::Value;
+
+/// Greeting sent before the login prompt, whatever the transport.
+pub const WELCOME_MESSAGE: &str = "\r\n&YWelcome to DeltaMUD!&n\r\n\r\n";
+
+/// Messages a connection's writer task may fall behind by. Past this,
+/// text for that client is discarded rather than stalling the game loop.
+pub const OUTPUT_QUEUE_SIZE: usize = 1024;
+
+/// Queue slots only control messages (compression switches, flushes,
+/// closes) may fill, so one always fits behind a backed-up client's text.
+const CONTROL_RESERVE: usize = 8;
+
+/// Lines a connection may have waiting to run before further input is
+/// thrown away as a flood.
+pub const MAX_QUEUED_INPUT: usize = 50;
+
+static NEXT_CONN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Allocate a connection id. Shared by every listener so telnet and
+/// WebSocket sessions never collide in the game's connection table.
+pub fn next_conn_id() -> u64 {
+    NEXT_CONN_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// How a client reached us. Decides which telnet options are worth
+/// offering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Telnet,
+    Tls,
+    WebSocket,
+}
 
 // Connection states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,35 +77,80 @@ pub enum ConnectionState {
     Close,
 }
 
-// Color codes
-pub const COLOR_CODES: &[(&str, &str)] = &[
-    ("&n", "\x1b[0m"),     // Normal
-    ("&r", "\x1b[0;31m"),  // Red
-    ("&g", "\x1b[0;32m"),  // Green
-    ("&y", "\x1b[0;33m"),  // Yellow
-    ("&b", "\x1b[0;34m"),  // Blue
-    ("&m", "\x1b[0;35m"),  // Magenta
-    ("&c", "\x1b[0;36m"),  // Cyan
-    ("&w", "\x1b[0;37m"),  // White
-    ("&R", "\x1b[1;31m"),  // Bright Red
-    ("&G", "\x1b[1;32m"),  // Bright Green
-    ("&Y", "\x1b[1;33m"),  // Bright Yellow
-    ("&B", "\x1b[1;34m"),  // Bright Blue
-    ("&M", "\x1b[1;35m"),  // Bright Magenta
-    ("&C", "\x1b[1;36m"),  // Bright Cyan
-    ("&W", "\x1b[1;37m"),  // Bright White
+// Color codes: (code, 16-color ANSI, xterm-256 index, 24-bit RGB). The
+// 256-color and truecolor renderings use the classic VGA palette so a
+// color looks the same whichever depth the client negotiated.
+pub type ColorCode = (&'static str, &'static str, u8, (u8, u8, u8));
+
+pub const COLOR_CODES: &[ColorCode] = &[
+    ("&n", "\x1b[0m", 0, (0, 0, 0)),              // Normal
+    ("&r", "\x1b[0;31m", 124, (170, 0, 0)),       // Red
+    ("&g", "\x1b[0;32m", 34, (0, 170, 0)),        // Green
+    ("&y", "\x1b[0;33m", 130, (170, 85, 0)),      // Yellow
+    ("&b", "\x1b[0;34m", 19, (0, 0, 170)),        // Blue
+    ("&m", "\x1b[0;35m", 127, (170, 0, 170)),     // Magenta
+    ("&c", "\x1b[0;36m", 37, (0, 170, 170)),      // Cyan
+    ("&w", "\x1b[0;37m", 248, (170, 170, 170)),   // White
+    ("&R", "\x1b[1;31m", 203, (255, 85, 85)),     // Bright Red
+    ("&G", "\x1b[1;32m", 83, (85, 255, 85)),      // Bright Green
+    ("&Y", "\x1b[1;33m", 227, (255, 255, 85)),    // Bright Yellow
+    ("&B", "\x1b[1;34m", 63, (85, 85, 255)),      // Bright Blue
+    ("&M", "\x1b[1;35m", 207, (255, 85, 255)),    // Bright Magenta
+    ("&C", "\x1b[1;36m", 87, (85, 255, 255)),     // Bright Cyan
+    ("&W", "\x1b[1;37m", 231, (255, 255, 255)),   // Bright White
 ];
 
 pub struct Connection {
     pub id: u64,
     pub addr: SocketAddr,
+    /// Socket descriptor for plain telnet, which is what copyover hands
+    /// to the new process.
+    pub fd: Option<RawFd>,
     pub state: ConnectionState,
     pub character: Option<Arc<RwLock<Character>>>,
     pub original: Option<Arc<RwLock<Character>>>,  // For switch command
     
     // I/O channels
-    pub output_tx: mpsc::Sender<String>,
+    pub output_tx: mpsc::Sender<Outbound>,
     pub input_rx: Option<mpsc::Receiver<String>>,
+
+    // Telnet options negotiated with the client
+    pub telnet: TelnetOptions,
+    pub terminal: TerminalInfo,
+
+    // GMCP modules the client asked for, and the last vitals/status pushed
+    // so unchanged values aren't resent every tick
+    pub gmcp_supports: Vec<String>,
+    last_gmcp_vitals: Option<Value>,
+    last_gmcp_status: Option<Value>,
+
+    // When the client last sent a line, for idle timeouts
+    pub last_input: Instant,
+
+    // Lines received but not yet run. One runs per pulse, and none while
+    // the character is lagged (get_from_q and d->wait in
+    // /web/deltamud/src/comm.c). The flag marks lines an alias expanded
+    // into, which aren't expanded again.
+    input_queue: VecDeque<(String, bool)>,
+    wait: u32,
+    input_flooded: bool,
+
+    // Set when text was dropped because the client fell behind
+    output_overflowed: AtomicBool,
+
+    // Long output waiting at a paging prompt
+    pub pager: Option<Pager>,
+
+    // The help entry being edited with hedit
+    pub hedit: Option<Hedit>,
+
+    // Recent commands, for ! and ^old^new
+    pub history: History,
+
+    // MSDP variables the client asked to have reported, with the value
+    // last sent for each
+    pub msdp_reported: Vec<String>,
+    msdp_last: HashMap<String, MsdpValue>,
     
     // Temporary data during character creation
     pub temp_name: Option<String>,
@@ -61,15 +158,37 @@ pub struct Connection {
 }
 
 impl Connection {
-    pub fn new(id: u64, addr: SocketAddr, output_tx: mpsc::Sender<String>) -> Self {
+    pub fn new(id: u64, addr: SocketAddr, output_tx: mpsc::Sender<Outbound>, transport: Transport) -> Self {
+        let mut telnet = TelnetOptions::new();
+        // The WebSocket writer has no MCCP2, so a client asking for it
+        // would be told the stream is compressed and then get plain bytes
+        if transport == Transport::WebSocket {
+            telnet.refuse_local(TELOPT_COMPRESS2);
+        }
         Connection {
             id,
             addr,
+            fd: None,
             state: ConnectionState::GetName,
             character: None,
             original: None,
             output_tx,
             input_rx: None,
+            telnet,
+            terminal: TerminalInfo::new(),
+            gmcp_supports: Vec::new(),
+            last_gmcp_vitals: None,
+            last_gmcp_status: None,
+            last_input: Instant::now(),
+            input_queue: VecDeque::new(),
+            wait: 0,
+            input_flooded: false,
+            output_overflowed: AtomicBool::new(false),
+            pager: None,
+            hedit: None,
+            history: History::new(),
+            msdp_reported: Vec::new(),
+            msdp_last: HashMap::new(),
             temp_name: None,
             temp_password: None,
         }
@@ -77,7 +196,380 @@ impl Connection {
     
     pub async fn send(&self, message: &str) -> Result<()> {
         let processed = self.process_color_codes(message);
-        self.output_tx.send(processed).await?;
+        self.queue(Outbound::Text(processed)).await
+    }
+
+    /// Send raw protocol bytes (telnet negotiation) with no color processing.
+    pub async fn send_raw(&self, bytes: Vec<u8>) -> Result<()> {
+        self.queue(Outbound::Raw(bytes)).await
+    }
+
+    /// Begin an MCCP2 stream. The writer sends the SB marker as the last
+    /// uncompressed bytes and switches to zlib for everything after it.
+    async fn start_compression(&self) -> Result<()> {
+        self.queue(Outbound::StartCompression).await
+    }
+
+    /// Finish any compressed stream and wait (briefly) until everything
+    /// queued so far has reached the socket, so a copyover can hand the
+    /// socket over at a clean byte boundary.
+    pub async fn drain(&self) -> Result<()> {
+        self.queue(Outbound::EndCompression).await?;
+        let (ack_tx, ack_rx) = oneshot::channel();
+        self.queue(Outbound::Flush(ack_tx)).await?;
+        let _ = timeout(Duration::from_secs(2), ack_rx).await;
+        Ok(())
+    }
+
+    /// Snapshot the session for copyover. Needs a plain telnet socket and
+    /// a character in the game.
+    pub fn save_for_copyover(&self, fd: RawFd, room: RoomVnum) -> Option<SavedConnection> {
+        let ch = self.character.as_ref()?;
+        Some(SavedConnection {
+            fd,
+            addr: self.addr,
+            name: ch.read().get_name().to_string(),
+            room,
+            telnet: self.telnet.clone(),
+            terminal: self.terminal.clone(),
+            gmcp_supports: self.gmcp_supports.clone(),
+            msdp_reported: self.msdp_reported.clone(),
+        })
+    }
+
+    /// Pick a session back up after copyover: negotiated options and
+    /// terminal details carry over, so the client needn't renegotiate.
+    /// Compression was ended before the exec and restarts here.
+    pub async fn restore_from_copyover(&mut self, saved: SavedConnection) -> Result<()> {
+        self.fd = Some(saved.fd);
+        self.telnet = saved.telnet;
+        self.terminal = saved.terminal;
+        self.gmcp_supports = saved.gmcp_supports;
+        self.msdp_reported = saved.msdp_reported;
+        if self.telnet.local_enabled(TELOPT_COMPRESS2) {
+            self.start_compression().await?;
+        }
+        Ok(())
+    }
+
+    /// Hand output to the writer task without ever waiting on it, so a
+    /// client that stops reading can't hold up the game loop. If the
+    /// socket is already gone the output is dropped; the Disconnect
+    /// message is on its way and the game loop shouldn't fail over it.
+    async fn queue(&self, msg: Outbound) -> Result<()> {
+        match msg {
+            // Text and protocol data (GMCP, MSDP, negotiation) are thrown
+            // away when the client isn't keeping up, like the **OVERFLOW**
+            // cutoff in /web/deltamud/src/comm.c. They stop short of the
+            // reserved slots.
+            Outbound::Text(_) | Outbound::Raw(_) => {
+                if self.output_overflowed.load(Ordering::Relaxed) {
+                    if self.output_tx.capacity() <= CONTROL_RESERVE {
+                        return Ok(());
+                    }
+                    let notice = Outbound::Text("**OVERFLOW**\r\n".to_string());
+                    if self.output_tx.try_send(notice).is_err() {
+                        return Ok(());
+                    }
+                    self.output_overflowed.store(false, Ordering::Relaxed);
+                }
+                if self.output_tx.capacity() <= CONTROL_RESERVE {
+                    warn!("Connection {}: output queue full, dropping output", self.id);
+                    self.output_overflowed.store(true, Ordering::Relaxed);
+                    return Ok(());
+                }
+                if self.output_tx.try_send(msg).is_err() {
+                    debug!("Connection {}: output dropped, socket closed", self.id);
+                }
+            }
+            // Compression switches, flushes and closes change what the
+            // writer does with everything after them, so they have to
+            // arrive, in order. The reserve keeps room for them.
+            msg => match self.output_tx.try_send(msg) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!("Connection {}: writer not responding, control message lost", self.id);
+                }
+                Err(TrySendError::Closed(_)) => {
+                    debug!("Connection {}: output dropped, socket closed", self.id);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Queue a line of input to run when the connection is ready. Lines
+    /// past MAX_QUEUED_INPUT are dropped, with one warning per flood.
+    pub async fn queue_input(&mut self, line: String) -> Result<()> {
+        if self.input_queue.len() >= MAX_QUEUED_INPUT {
+            if !self.input_flooded {
+                self.input_flooded = true;
+                warn!("Connection {}: input flood, dropping lines", self.id);
+                self.send_line("Too much input at once; the rest was discarded.").await?;
+            }
+            return Ok(());
+        }
+        self.input_queue.push_back((line, false));
+        Ok(())
+    }
+
+    /// Take the next queued line if nothing is holding it back: another
+    /// line already ran this pulse, or the character is lagged.
+    pub fn next_input(&mut self) -> Option<(String, bool)> {
+        let lagged = self.character.as_ref().is_some_and(|ch| ch.read().wait > 0);
+        if self.wait > 0 || lagged {
+            return None;
+        }
+        let line = self.input_queue.pop_front()?;
+        self.wait = 1;
+        if self.input_queue.is_empty() {
+            self.input_flooded = false;
+        }
+        Some(line)
+    }
+
+    /// Put the commands an alias expanded into at the front of the queue,
+    /// so they run next and in order.
+    pub fn queue_alias_lines(&mut self, lines: Vec<String>) {
+        for line in lines.into_iter().rev() {
+            self.input_queue.push_front((line, true));
+        }
+    }
+
+    /// Count down one pulse of wait, for the connection and its character.
+    pub fn pulse_wait(&mut self) {
+        self.wait = self.wait.saturating_sub(1);
+        if let Some(ch) = &self.character {
+            let mut ch = ch.write();
+            ch.wait = ch.wait.saturating_sub(1);
+        }
+    }
+
+    /// Whether the current state is a password prompt.
+    pub fn is_password_prompt(&self) -> bool {
+        matches!(
+            self.state,
+            ConnectionState::GetOldPassword
+                | ConnectionState::GetNewPassword
+                | ConnectionState::ConfirmPassword
+        )
+    }
+
+    /// Offer IAC WILL ECHO while a password prompt is up, so the client
+    /// stops echoing what is typed, and withdraw it with IAC WONT ECHO once
+    /// the state moves on. Mirrors echo_off/echo_on in
+    /// /web/deltamud/src/comm.c, which nanny() calls around every password
+    /// prompt.
+    pub async fn update_echo(&mut self) -> Result<()> {
+        let bytes = if self.is_password_prompt() {
+            self.telnet.request_local(TELOPT_ECHO)
+        } else {
+            self.telnet.disable_local(TELOPT_ECHO)
+        };
+        if let Some(bytes) = bytes {
+            self.send_raw(bytes).await?;
+        }
+        Ok(())
+    }
+
+    /// Open negotiation with a freshly connected client: offer compression,
+    /// GMCP and MSDP, and ask for its window size and terminal type.
+    /// WebSocket clients don't get MCCP2 (refused in Connection::new),
+    /// which web clients can't inflate. Their output goes uncompressed,
+    /// as tokio-tungstenite doesn't do permessage-deflate either.
+    pub async fn start_negotiation(&mut self) -> Result<()> {
+        for opt in [TELOPT_COMPRESS2, TELOPT_GMCP, TELOPT_MSDP] {
+            if let Some(bytes) = self.telnet.request_local(opt) {
+                self.send_raw(bytes).await?;
+            }
+        }
+        for opt in [TELOPT_NAWS, TELOPT_TTYPE] {
+            if let Some(bytes) = self.telnet.request_remote(opt) {
+                self.send_raw(bytes).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Process a negotiation command or subnegotiation from the client,
+    /// answering it and updating the negotiated option set.
+    pub async fn handle_telnet(&mut self, event: TelnetEvent) -> Result<()> {
+        if let TelnetEvent::Subnegotiation(opt, payload) = event {
+            return self.handle_subnegotiation(opt, &payload).await;
+        }
+
+        let negotiation = self.telnet.receive(&event);
+        if let Some(reply) = negotiation.reply {
+            self.send_raw(reply).await?;
+        }
+        if let Some((side, opt, enabled)) = negotiation.changed {
+            debug!("Connection {}: telnet option {} ({:?}) {}",
+                self.id, opt, side, if enabled { "on" } else { "off" });
+            match (side, opt) {
+                (OptionSide::Remote, TELOPT_TTYPE) if enabled => {
+                    self.send_raw(telnet::subnegotiation(TELOPT_TTYPE, &[TTYPE_SEND])).await?;
+                }
+                // MCCP2: the SB marker is the last uncompressed byte; the
+                // writer switches to zlib for everything queued after it.
+                (OptionSide::Local, TELOPT_COMPRESS2) => {
+                    if enabled {
+                        self.start_compression().await?;
+                    } else {
+                        self.queue(Outbound::EndCompression).await?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_subnegotiation(&mut self, opt: u8, payload: &[u8]) -> Result<()> {
+        match opt {
+            TELOPT_NAWS => {
+                self.terminal.apply_naws(payload);
+                debug!("Connection {}: window {}x{}",
+                    self.id, self.terminal.width, self.terminal.height);
+            }
+            TELOPT_TTYPE => {
+                if self.terminal.apply_ttype(payload) {
+                    self.send_raw(telnet::subnegotiation(TELOPT_TTYPE, &[TTYPE_SEND])).await?;
+                } else {
+                    debug!("Connection {}: terminal {:?}/{:?}, color {:?}, utf8 {}, screen reader {}",
+                        self.id, self.terminal.client_name, self.terminal.terminal_type,
+                        self.terminal.color, self.terminal.utf8, self.terminal.screen_reader);
+                }
+            }
+            TELOPT_GMCP => {
+                let (package, data) = gmcp::decode(payload);
+                match package.to_lowercase().as_str() {
+                    "core.supports.set" => {
+                        self.gmcp_supports = data.as_ref().map(gmcp::module_names).unwrap_or_default();
+                    }
+                    "core.supports.add" => {
+                        for module in data.as_ref().map(gmcp::module_names).unwrap_or_default() {
+                            if !self.gmcp_supports.contains(&module) {
+                                self.gmcp_supports.push(module);
+                            }
+                        }
+                    }
+                    "core.supports.remove" => {
+                        let removed = data.as_ref().map(gmcp::module_names).unwrap_or_default();
+                        self.gmcp_supports.retain(|m| !removed.contains(m));
+                    }
+                    "core.ping" => {
+                        self.send_gmcp("Core.Ping", &Value::Null).await?;
+                    }
+                    _ => debug!("Connection {}: ignoring GMCP {}", self.id, package),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Push a GMCP message if the client negotiated GMCP and subscribed to
+    /// the package's module.
+    pub async fn send_gmcp(&self, package: &str, data: &Value) -> Result<()> {
+        if self.telnet.local_enabled(TELOPT_GMCP) && gmcp::is_supported(&self.gmcp_supports, package) {
+            self.send_raw(gmcp::encode(package, data)).await?;
+        }
+        Ok(())
+    }
+
+    /// Send MSDP variables if the client negotiated MSDP.
+    pub async fn send_msdp(&self, vars: &[(&str, MsdpValue)]) -> Result<()> {
+        if self.telnet.local_enabled(TELOPT_MSDP) && !vars.is_empty() {
+            self.send_raw(msdp::encode(vars)).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply LIST/REPORT/UNREPORT/SEND/RESET commands from the client.
+    /// `vars` is the current value of every reportable variable.
+    pub async fn handle_msdp(&mut self, payload: &[u8], vars: &[(&'static str, MsdpValue)]) -> Result<()> {
+        for (command, args) in msdp::decode(payload) {
+            match command.as_str() {
+                "LIST" => {
+                    for name in &args {
+                        if let Some(value) = msdp::list(name, &self.msdp_reported) {
+                            self.send_msdp(&[(name.as_str(), value)]).await?;
+                        }
+                    }
+                }
+                "REPORT" => {
+                    for name in args {
+                        if msdp::REPORTABLE_VARIABLES.contains(&name.as_str())
+                            && !self.msdp_reported.contains(&name)
+                        {
+                            self.msdp_last.remove(&name);
+                            self.msdp_reported.push(name);
+                        }
+                    }
+                    self.report_msdp(vars).await?;
+                }
+                "UNREPORT" => {
+                    self.msdp_reported.retain(|n| !args.contains(n));
+                    self.msdp_last.retain(|n, _| !args.contains(n));
+                }
+                "RESET" => {
+                    if args.iter().any(|a| a == "REPORTABLE_VARIABLES" || a == "REPORTED_VARIABLES") {
+                        self.msdp_reported.clear();
+                        self.msdp_last.clear();
+                    }
+                }
+                "SEND" => {
+                    let wanted: Vec<(&str, MsdpValue)> = vars.iter()
+                        .filter(|(name, _)| args.iter().any(|a| a == name))
+                        .cloned()
+                        .collect();
+                    self.send_msdp(&wanted).await?;
+                }
+                _ => debug!("Connection {}: ignoring MSDP {}", self.id, command),
+            }
+        }
+        Ok(())
+    }
+
+    /// Push the reported MSDP variables whose value changed since the last
+    /// push.
+    pub async fn report_msdp(&mut self, vars: &[(&'static str, MsdpValue)]) -> Result<()> {
+        if !self.telnet.local_enabled(TELOPT_MSDP) || self.msdp_reported.is_empty() {
+            return Ok(());
+        }
+        let mut changed = Vec::new();
+        for (name, value) in vars {
+            if self.msdp_reported.iter().any(|n| n == name)
+                && self.msdp_last.get(*name) != Some(value)
+            {
+                self.msdp_last.insert(name.to_string(), value.clone());
+                changed.push((*name, value.clone()));
+            }
+        }
+        self.send_msdp(&changed).await
+    }
+
+    /// Push Char.Vitals and Char.Status for the attached character, skipping
+    /// whichever hasn't changed since the last push.
+    pub async fn update_gmcp_vitals(&mut self) -> Result<()> {
+        if !self.telnet.local_enabled(TELOPT_GMCP) {
+            return Ok(());
+        }
+        let (vitals, status) = match &self.character {
+            Some(ch) => {
+                let ch = ch.read();
+                (gmcp::char_vitals(&ch), gmcp::char_status(&ch))
+            }
+            None => return Ok(()),
+        };
+        if self.last_gmcp_vitals.as_ref() != Some(&vitals) {
+            self.send_gmcp("Char.Vitals", &vitals).await?;
+            self.last_gmcp_vitals = Some(vitals);
+        }
+        if self.last_gmcp_status.as_ref() != Some(&status) {
+            self.send_gmcp("Char.Status", &status).await?;
+            self.last_gmcp_status = Some(status);
+        }
         Ok(())
     }
     
@@ -85,7 +577,52 @@ impl Connection {
         self.send(&format!("{}\r\n", message)).await
     }
     
+    /// Send text a page at a time if it's longer than the character's
+    /// page_length (page_string in /web/deltamud/src/modify.c).
+    pub async fn page_string(&mut self, text: &str) -> Result<()> {
+        let page_length = match &self.character {
+            Some(ch) => ch.read().player.page_length as usize,
+            None => 0,
+        };
+        if page_length == 0 {
+            return self.send(text).await;
+        }
+        let pager = Pager::new(text, page_length, self.terminal.width as usize);
+        if pager.page_count() <= 1 {
+            return self.send(text).await;
+        }
+        self.pager = Some(pager);
+        self.show_page("").await
+    }
+
+    /// Answer input typed at the paging prompt (show_string).
+    pub async fn show_page(&mut self, input: &str) -> Result<()> {
+        let pager = match self.pager.as_mut() {
+            Some(pager) => pager,
+            None => return Ok(()),
+        };
+        match pager.respond(input) {
+            Ok(Some(page)) => {
+                if pager.finished() {
+                    self.pager = None;
+                }
+                self.send(&page).await
+            }
+            Ok(None) => {
+                self.pager = None;
+                Ok(())
+            }
+            Err(msg) => self.send_line(msg).await,
+        }
+    }
+    
     pub async fn send_prompt(&self) -> Result<()> {
+        if let Some(pager) = &self.pager {
+            return self.send(&pager.prompt()).await;
+        }
+        if let Some(hedit) = &self.hedit {
+            return self.send(hedit.prompt()).await;
+        }
         match self.state {
             ConnectionState::Playing => {
                 if let Some(ch) = &self.character {
@@ -144,73 +681,141 @@ impl Connection {
         Ok(())
     }
     
+    /// Expand &-color codes for the color depth this client negotiated.
+    /// Clients with no color support (or a screen reader) get the codes
+    /// stripped instead of raw escape sequences.
     fn process_color_codes(&self, text: &str) -> String {
+        let level = self.terminal.color;
         let mut result = text.to_string();
-        for (code, ansi) in COLOR_CODES {
-            result = result.replace(code, ansi);
+        for (code, ansi, xterm, (r, g, b)) in COLOR_CODES {
+            let replacement = match level {
+                ColorLevel::None => String::new(),
+                ColorLevel::Ansi16 => ansi.to_string(),
+                _ if *code == "&n" => ansi.to_string(),
+                ColorLevel::Ansi256 => format!("\x1b[38;5;{}m", xterm),
+                ColorLevel::TrueColor => format!("\x1b[38;2;{};{};{}m", r, g, b),
+            };
+            result = result.replace(code, &replacement);
         }
         result
     }
     
-    pub fn close(&mut self) {
+    /// Mark the connection closed and have the writer hang up once the
+    /// output queued so far is sent. Echo is handed back first so a client
+    /// dropped at a password prompt isn't left with input hidden.
+    pub async fn close(&mut self) -> Result<()> {
         self.state = ConnectionState::Close;
+        self.update_echo().await?;
+        self.queue(Outbound::Close).await
     }
 }
 
 // Handles individual client connections
-pub async fn handle_client(
-    stream: TcpStream,
+/// Run a telnet session over any byte stream: a plain TCP socket (with its
+/// descriptor, for copyover) or one already wrapped in TLS.
+pub async fn handle_client<S>(
+    stream: S,
     addr: SocketAddr,
     conn_id: u64,
+    transport: Transport,
+    fd: Option<RawFd>,
+    game_tx: mpsc::Sender<GameMessage>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let opening = move |output_tx| GameMessage::NewConnection {
+        id: conn_id,
+        addr,
+        output_tx,
+        transport,
+        fd,
+    };
+    run_session(stream, conn_id, opening, Some(WELCOME_MESSAGE), game_tx).await
+}
+
+/// Run a telnet session inherited across a copyover.
+pub async fn resume_client(
+    stream: tokio::net::TcpStream,
+    conn_id: u64,
+    saved: SavedConnection,
+    elapsed: Duration,
     game_tx: mpsc::Sender<GameMessage>,
 ) -> Result<()> {
-    let (reader, mut writer) = stream.into_split();
-    let mut reader = BufReader::new(reader);
+    let opening = move |output_tx| GameMessage::Recovered {
+        id: conn_id,
+        output_tx,
+        saved: Box::new(saved),
+        elapsed,
+    };
+    run_session(stream, conn_id, opening, None, game_tx).await
+}
+
+async fn run_session<S, F>(
+    stream: S,
+    conn_id: u64,
+    opening: F,
+    greeting: Option<&str>,
+    game_tx: mpsc::Sender<GameMessage>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+    F: FnOnce(mpsc::Sender<Outbound>) -> GameMessage,
+{
+    let (mut reader, writer) = tokio::io::split(stream);
     
     // Create output channel
-    let (output_tx, mut output_rx) = mpsc::channel(100);
+    let (output_tx, mut output_rx) = mpsc::channel(OUTPUT_QUEUE_SIZE);
     
     // Notify game of new connection
-    game_tx.send(GameMessage::NewConnection {
-        id: conn_id,
-        addr,
-        output_tx: output_tx.clone(),
-    }).await?;
+    game_tx.send(opening(output_tx.clone())).await?;
     
     // Send welcome message
-    let welcome = "\r\n&YWelcome to DeltaMUD!&n\r\n\r\n";
-    output_tx.send(welcome.to_string()).await?;
+    if let Some(greeting) = greeting {
+        output_tx.send(Outbound::Text(greeting.to_string())).await?;
+    }
     
     // Spawn task to handle output
-    let write_handle = tokio::spawn(async move {
+    let mut write_handle = tokio::spawn(async move {
+        let mut output = OutputWriter::new(writer);
         while let Some(msg) = output_rx.recv().await {
-            if writer.write_all(msg.as_bytes()).await.is_err() {
-                break;
+            match msg {
+                Outbound::Close => {
+                    output.shutdown().await;
+                    break;
+                }
+                Outbound::Flush(ack) => {
+                    let _ = ack.send(());
+                    continue;
+                }
+                _ => {}
             }
-            if writer.flush().await.is_err() {
+            if output.write(msg).await.is_err() {
                 break;
             }
         }
     });
     
-    // Read input
-    let mut line = String::new();
+    // Read input, stripping telnet negotiation out of the byte stream.
+    // Stop when the writer does too, since that means the game hung up.
+    let mut parser = TelnetParser::new();
+    let mut buf = [0u8; 4096];
     loop {
-        line.clear();
-        match reader.read_line(&mut line).await {
-            Ok(0) => break, // EOF
-            Ok(_) => {
-                let input = line.trim().to_string();
-                // Pass empty lines through too — Enter on a prompt (MOTD, etc.)
-                // is meaningful. Command handlers guard against empty input.
-                game_tx.send(GameMessage::Input {
-                    conn_id,
-                    input,
-                }).await?;
-            }
-            Err(_) => break,
+        let n = tokio::select! {
+            read = reader.read(&mut buf) => match read {
+                Ok(0) => break, // EOF
+                Ok(n) => n,
+                Err(_) => break,
+            },
+            _ = &mut write_handle => break,
+        };
+        for event in parser.parse(&buf[..n]) {
+            forward_event(&game_tx, conn_id, event).await?;
         }
     }
+    if let Some(event) = parser.flush() {
+        forward_event(&game_tx, conn_id, event).await?;
+    }
     
     // Notify disconnection
     game_tx.send(GameMessage::Disconnect { conn_id }).await?;
@@ -220,18 +825,118 @@ pub async fn handle_client(
     Ok(())
 }
 
+pub async fn forward_event(
+    game_tx: &mpsc::Sender<GameMessage>,
+    conn_id: u64,
+    event: TelnetEvent,
+) -> Result<()> {
+    let msg = match event {
+        // Pass empty lines through too — Enter on a prompt (MOTD, etc.)
+        // is meaningful. Command handlers guard against empty input.
+        TelnetEvent::Line(input) => GameMessage::Input { conn_id, input },
+        other => GameMessage::Telnet { conn_id, event: other },
+    };
+    game_tx.send(msg).await?;
+    Ok(())
+}
+
+/// A chunk of output queued for a client's writer task.
+#[derive(Debug)]
+pub enum Outbound {
+    /// Game text, already color-processed.
+    Text(String),
+    /// Protocol bytes such as telnet negotiation, written verbatim.
+    Raw(Vec<u8>),
+    /// Send the MCCP2 start marker and compress everything after it.
+    StartCompression,
+    /// Finish the MCCP2 stream and go back to plain output.
+    EndCompression,
+    /// Flush and close the socket.
+    Close,
+    /// Acknowledge once everything queued before it has been written.
+    Flush(oneshot::Sender<()>),
+}
+
+/// The socket side of a connection's output. Owns the optional MCCP2
+/// compressor so the stream starts exactly where the game queued
+/// StartCompression, no matter how much output was already in flight.
+struct OutputWriter<W> {
+    writer: W,
+    compressor: Option<ZlibEncoder<Vec<u8>>>,
+}
+
+impl<W: AsyncWrite + Unpin> OutputWriter<W> {
+    fn new(writer: W) -> Self {
+        OutputWriter { writer, compressor: None }
+    }
+
+    async fn write(&mut self, msg: Outbound) -> std::io::Result<()> {
+        let bytes = match msg {
+            Outbound::Text(text) => self.encode(text.as_bytes())?,
+            Outbound::Raw(raw) => self.encode(&raw)?,
+            Outbound::StartCompression => {
+                if self.compressor.is_some() {
+                    return Ok(());
+                }
+                self.compressor = Some(ZlibEncoder::new(Vec::new(), Compression::default()));
+                telnet::subnegotiation(TELOPT_COMPRESS2, &[])
+            }
+            Outbound::Flush(_) => return Ok(()),
+            Outbound::EndCompression | Outbound::Close => match self.compressor.take() {
+                Some(z) => z.finish()?,
+                None => return Ok(()),
+            },
+        };
+        self.writer.write_all(&bytes).await?;
+        self.writer.flush().await
+    }
+
+    /// End any compressed stream cleanly and close the socket.
+    async fn shutdown(&mut self) {
+        if self.write(Outbound::EndCompression).await.is_ok() {
+            let _ = self.writer.shutdown().await;
+        }
+    }
+
+    /// Pass bytes through the compressor if one is running, sync-flushing
+    /// so the client can decode each message as soon as it arrives.
+    fn encode(&mut self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match &mut self.compressor {
+            Some(z) => {
+                z.write_all(bytes)?;
+                z.flush()?;
+                Ok(std::mem::take(z.get_mut()))
+            }
+            None => Ok(bytes.to_vec()),
+        }
+    }
+}
+
 // Messages sent to the main game loop
 #[derive(Debug)]
 pub enum GameMessage {
     NewConnection {
         id: u64,
         addr: SocketAddr,
-        output_tx: mpsc::Sender<String>,
+        output_tx: mpsc::Sender<Outbound>,
+        transport: Transport,
+        fd: Option<RawFd>,
+    },
+    /// A session carried over from before a copyover.
+    Recovered {
+        id: u64,
+        output_tx: mpsc::Sender<Outbound>,
+        saved: Box<SavedConnection>,
+        elapsed: Duration,
     },
     Input {
         conn_id: u64,
         input: String,
     },
+    Telnet {
+        conn_id: u64,
+        event: TelnetEvent,
+    },
     Disconnect {
         conn_id: u64,
     },
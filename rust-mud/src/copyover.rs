@@ -0,0 +1,135 @@
+// Hot reboot (copyover)
+//
+// Port of do_copyover and copyover_recover (/web/deltamud/src/act.wizard.c,
+// /web/deltamud/src/comm.c). The listening socket and every playing telnet
+// socket are dup'ed so they survive exec, the descriptors and who was on
+// them are written to lib/copyover.dat, and the binary execs itself with
+// --copyover. On boot the new process reads the file back, rebuilds a
+// Connection around each inherited socket and puts everyone back in the
+// room they stood in. TLS and WebSocket sessions carry state that can't be
+// handed over, so those players are asked to reconnect instead.
+
+use crate::telnet::{TelnetOptions, TerminalInfo};
+use crate::types::RoomVnum;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use log::warn;
+use std::os::fd::RawFd;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const COPYOVER_FILE: &str = "copyover.dat";
+pub const BENCHMARK_FILE: &str = "copyover.benchmark";
+
+/// Command-line flag telling a freshly exec'ed server to recover.
+pub const COPYOVER_FLAG: &str = "--copyover";
+
+/// Everything the new process needs to pick up where the old one stopped.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CopyoverState {
+    pub listener_fd: RawFd,
+    pub connections: Vec<SavedConnection>,
+    /// Zone ages, so resets keep their schedule across the reboot.
+    pub zone_ages: Vec<(i32, i32)>,
+}
+
+/// One playing telnet session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedConnection {
+    pub fd: RawFd,
+    pub addr: SocketAddr,
+    pub name: String,
+    pub room: RoomVnum,
+    pub telnet: TelnetOptions,
+    pub terminal: TerminalInfo,
+    pub gmcp_supports: Vec<String>,
+    pub msdp_reported: Vec<String>,
+}
+
+/// Duplicate a socket descriptor without close-on-exec, so it outlives
+/// both the tokio stream that owns the original and the exec.
+pub fn inherit_fd(fd: RawFd) -> Result<RawFd> {
+    let new_fd = unsafe { libc::dup(fd) };
+    if new_fd < 0 {
+        return Err(anyhow!("dup({}) failed: {}", fd, std::io::Error::last_os_error()));
+    }
+    Ok(new_fd)
+}
+
+/// Take ownership of a descriptor inherited across exec, turning
+/// close-on-exec back on so the next copyover doesn't carry it along as
+/// an orphan beside its new dup.
+pub fn reclaim_fd(fd: RawFd) -> Result<RawFd> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) } < 0 {
+        return Err(anyhow!("fcntl({}) failed: {}", fd, std::io::Error::last_os_error()));
+    }
+    Ok(fd)
+}
+
+/// Close a descriptor dup'ed for a copyover that isn't going ahead.
+pub fn close_fd(fd: RawFd) {
+    unsafe { libc::close(fd) };
+}
+
+/// Close every descriptor dup'ed into `state`, when the copyover is
+/// called off after all.
+pub fn release(state: &CopyoverState) {
+    close_fd(state.listener_fd);
+    for saved in &state.connections {
+        close_fd(saved.fd);
+    }
+}
+
+/// Write the state file and the benchmark timestamp.
+pub fn save(lib_path: &str, state: &CopyoverState) -> Result<()> {
+    let path = state_path(lib_path);
+    let json = serde_json::to_string(state)?;
+    std::fs::write(&path, json).with_context(|| format!("writing {}", path.display()))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let bench = Path::new(lib_path).join(BENCHMARK_FILE);
+    if let Err(e) = std::fs::write(&bench, now.as_millis().to_string()) {
+        warn!("Could not write {}: {}", bench.display(), e);
+    }
+    Ok(())
+}
+
+/// Read the state file, deleting it first so a crash during recovery
+/// doesn't leave the next cold boot trying to recover too. Also returns
+/// how long the reboot took, from the benchmark file.
+pub fn load(lib_path: &str) -> Result<(CopyoverState, Duration)> {
+    let path = state_path(lib_path);
+    let json = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let _ = std::fs::remove_file(&path);
+    let state = serde_json::from_str(&json)?;
+
+    let elapsed = std::fs::read_to_string(Path::new(lib_path).join(BENCHMARK_FILE))
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .and_then(|started| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+            now.checked_sub(Duration::from_millis(started))
+        })
+        .unwrap_or_default();
+    Ok((state, elapsed))
+}
+
+/// Replace this process with the server binary, keeping the original
+/// arguments. Goes by argv[0] rather than /proc/self/exe so a binary
+/// rebuilt in place is the one that starts. Only returns if exec failed.
+pub fn exec() -> anyhow::Error {
+    let mut args = std::env::args();
+    let exe = match args.next() {
+        Some(exe) => exe,
+        None => return anyhow!("no argv[0] to exec"),
+    };
+    let args: Vec<String> = args.filter(|a| a != COPYOVER_FLAG).collect();
+    std::process::Command::new(exe).args(args).arg(COPYOVER_FLAG).exec().into()
+}
+
+fn state_path(lib_path: &str) -> PathBuf {
+    Path::new(lib_path).join(COPYOVER_FILE)
+}
@@ -1,5 +1,6 @@
-use mysql_async::{Pool, prelude::*, Row};
+use mysql_async::{Pool, prelude::*, Row, TxOpts};
 use crate::character::{Character, Affect};
+use crate::alias::Alias;
 use anyhow::{Result, anyhow};
 use sha2::{Sha256, Digest};
 use log::info;
@@ -106,6 +107,18 @@ impl Database {
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
         ", ()).await?;
         
+        // Create player_aliases table
+        conn.exec_drop(r"
+            CREATE TABLE IF NOT EXISTS player_aliases (
+                idnum INT NOT NULL,
+                seq INT NOT NULL,
+                alias VARCHAR(32) NOT NULL,
+                replacement VARCHAR(255) NOT NULL,
+                PRIMARY KEY (idnum, alias),
+                FOREIGN KEY (idnum) REFERENCES player_main(idnum) ON DELETE CASCADE
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+        ", ()).await?;
+        
         info!("Database tables initialized");
         Ok(())
     }
@@ -282,7 +295,7 @@ impl Database {
         conn.exec_drop(
             r"UPDATE player_main SET
                 room_vnum = ?, position = ?, act_flags = ?, affect_flags = ?,
-                last_logon = NOW()
+                host = ?, last_logon = NOW()
             WHERE idnum = ?",
             (
                 character.in_room.as_ref()
@@ -292,6 +305,7 @@ impl Database {
                 character.position as u8,
                 character.act_flags,
                 character.affect_flags,
+                &character.player.host,
                 character.id,
             )
         ).await?;
@@ -321,6 +335,32 @@ impl Database {
         Ok(())
     }
     
+    pub async fn load_aliases(&self, player_id: u64) -> Result<Vec<Alias>> {
+        let mut conn = self.pool.get_conn().await?;
+        let rows: Vec<(String, String)> = conn
+            .exec(
+                "SELECT alias, replacement FROM player_aliases WHERE idnum = ? ORDER BY seq",
+                (player_id,)
+            )
+            .await?;
+        Ok(rows.into_iter().map(|(name, replacement)| Alias { name, replacement }).collect())
+    }
+    
+    /// Replace a player's aliases in one transaction, so a failure
+    /// partway leaves the old set rather than none.
+    pub async fn save_aliases(&self, player_id: u64, aliases: &[Alias]) -> Result<()> {
+        let mut tx = self.pool.start_transaction(TxOpts::default()).await?;
+        tx.exec_drop("DELETE FROM player_aliases WHERE idnum = ?", (player_id,)).await?;
+        for (seq, alias) in aliases.iter().enumerate() {
+            tx.exec_drop(
+                "INSERT INTO player_aliases (idnum, seq, alias, replacement) VALUES (?, ?, ?, ?)",
+                (player_id, seq, &alias.name, &alias.replacement)
+            ).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    
     pub async fn verify_password(&self, name: &str, password: &str) -> Result<bool> {
         let mut conn = self.pool.get_conn().await?;
         
@@ -405,6 +445,8 @@ impl Database {
         character.position = unsafe { std::mem::transmute(pos) };
         character.act_flags = row.get("act_flags").unwrap();
         character.affect_flags = row.get("affect_flags").unwrap();
+        let host: Option<String> = row.get("host").unwrap_or(None);
+        character.player.host = host.unwrap_or_default();
         
         Ok(character)
     }
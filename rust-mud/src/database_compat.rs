@@ -1,7 +1,8 @@
 // Full database compatibility layer for original DeltaMUD schema
 
-use mysql_async::{Pool, prelude::*, Row};
+use mysql_async::{Pool, prelude::*, Row, TxOpts};
 use crate::character::{Character, Affect};
+use crate::alias::Alias;
 use anyhow::{Result, anyhow};
 use sha2::{Sha256, Digest};
 use log::warn;
@@ -145,6 +146,8 @@ impl PlayerMainRow {
         ch.player.time_played = self.played as i64;
         ch.player.weight = self.weight as u8;
         ch.player.height = self.height as u8;
+        ch.player.page_length = self.page_length.max(0) as u8;
+        ch.player.host = self.host.clone();
         
         // Stats
         ch.points.hit = self.hit as i32;
@@ -252,12 +255,12 @@ impl PlayerMainRow {
             prf: 0,
             aff: ch.affect_flags,
             
-            page_length: 24,
+            page_length: ch.player.page_length as i8,
             wimp_level: 0,
             freeze_level: 0,
             bad_pws: 0,
             invis_level: 0,
-            host: String::new(),
+            host: ch.player.host.clone(),
             
             clan_id: 0,
             clan_rank: 0,
@@ -406,11 +409,11 @@ impl CompatDatabase {
         conn.exec_drop(
             r"UPDATE player_main SET
                 act = ?, plr = ?, prf = ?, aff = ?, page_length = ?, wimp_level = ?,
-                freeze_level = ?, invis_level = ?, clan_id = ?, clan_rank = ?
+                freeze_level = ?, invis_level = ?, clan_id = ?, clan_rank = ?, host = ?
             WHERE idnum = ?",
             (
                 data.act, data.plr, data.prf, data.aff, data.page_length, data.wimp_level,
-                data.freeze_level, data.invis_level, data.clan_id, data.clan_rank,
+                data.freeze_level, data.invis_level, data.clan_id, data.clan_rank, &data.host,
                 data.idnum
             )
         ).await?;
@@ -540,6 +543,33 @@ impl CompatDatabase {
         })
     }
     
+    // Aliases live in player_aliases, one row per alias (the C code kept
+    // them in a per-player alias file)
+    pub async fn load_aliases_compat(&self, idnum: u64) -> Result<Vec<Alias>> {
+        let mut conn = self.pool.get_conn().await?;
+        let rows: Vec<(String, String)> = conn
+            .exec(
+                "SELECT alias, replacement FROM player_aliases WHERE idnum = ? ORDER BY seq",
+                (idnum,)
+            )
+            .await?;
+        Ok(rows.into_iter().map(|(name, replacement)| Alias { name, replacement }).collect())
+    }
+    
+    // All or nothing, like Database::save_aliases
+    pub async fn save_aliases_compat(&self, idnum: u64, aliases: &[Alias]) -> Result<()> {
+        let mut tx = self.pool.start_transaction(TxOpts::default()).await?;
+        tx.exec_drop("DELETE FROM player_aliases WHERE idnum = ?", (idnum,)).await?;
+        for (seq, alias) in aliases.iter().enumerate() {
+            tx.exec_drop(
+                "INSERT INTO player_aliases (idnum, seq, alias, replacement) VALUES (?, ?, ?, ?)",
+                (idnum, seq, &alias.name, &alias.replacement)
+            ).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    
     // Handle old crypt() passwords
     pub async fn verify_password_compat(&self, name: &str, password: &str) -> Result<bool> {
         let mut conn = self.pool.get_conn().await?;
@@ -1,6 +1,6 @@
 use crate::world::{World, Zone, MobileProto, ObjectProto, ResetCmd};
 use crate::room::{Room, Exit, RoomFlags};
-use crate::object::{WearFlags, ExtraFlags};
+use crate::object::{ObjectType, WearFlags, ExtraFlags};
 use crate::types::*;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -285,13 +285,13 @@ impl FileLoader {
                             // Read keywords
                             line.clear();
                             reader.read_line(&mut line)?;
-                            let keywords = line.trim_end_matches('~').to_string();
+                            let keywords = line.trim_end().trim_end_matches('~').to_string();
                             
                             // Read door info
                             line.clear();
                             reader.read_line(&mut line)?;
                             let parts: Vec<&str> = line.split_whitespace().collect();
-                            let exit_info = parts.get(0).unwrap_or(&"0").parse()?;
+                            let exit_info = Exit::flags_from_file(parts.get(0).unwrap_or(&"0").parse()?);
                             let key = parts.get(1).unwrap_or(&"-1").parse()?;
                             let to_room = parts.get(2).unwrap_or(&"0").parse()?;
                             
@@ -590,17 +590,17 @@ impl FileLoader {
                 // Read keywords
                 line.clear();
                 reader.read_line(&mut line)?;
-                let keywords = line.trim_end_matches('~').to_string();
+                let keywords = line.trim_end().trim_end_matches('~').to_string();
                 
                 // Read short description
                 line.clear();
                 reader.read_line(&mut line)?;
-                let short_desc = line.trim_end_matches('~').to_string();
+                let short_desc = line.trim_end().trim_end_matches('~').to_string();
                 
                 // Read long description
                 line.clear();
                 reader.read_line(&mut line)?;
-                let long_desc = line.trim_end_matches('~').to_string();
+                let long_desc = line.trim_end().trim_end_matches('~').to_string();
                 
                 // Read action description
                 line.clear();
@@ -612,7 +612,7 @@ impl FileLoader {
                 reader.read_line(&mut line)?;
                 let parts: Vec<&str> = line.split_whitespace().collect();
                 
-                let obj_type = parts.get(0).unwrap_or(&"9").parse::<u8>()?;
+                let obj_type = parts.get(0).unwrap_or(&"12").parse::<i32>()?;
                 let extra_flags = parts.get(1).unwrap_or(&"0").parse::<u64>()?;
                 let wear_flags = parts.get(2).unwrap_or(&"1").parse::<u32>()?;
                 
@@ -639,7 +639,7 @@ impl FileLoader {
                     name: keywords,
                     short_desc,
                     description: long_desc,
-                    obj_type: unsafe { std::mem::transmute(obj_type.min(17)) },
+                    obj_type: ObjectType::from_i32(obj_type),
                     wear_flags: WearFlags::from_bits_truncate(wear_flags),
                     extra_flags: ExtraFlags::from_bits_truncate(extra_flags),
                     weight,
@@ -1,18 +1,31 @@
 use crate::connection::{Connection, ConnectionState, GameMessage};
 use crate::world::World;
-use crate::room::Room;
+use crate::act::{Act, TO_ROOM};
 use crate::types::*;
 use crate::character::Character;
 use crate::object::{ExtraFlags, Object, ObjectType, WearFlags};
 use crate::DatabaseInterface;
 use crate::combat::{Combat, DeathResult, PULSE_VIOLENCE};
-use crate::magic::affect_update;
 use crate::commands::Commands;
+use crate::magic::affect_update;
+use crate::interpreter;
+use crate::alias::{self, Alias};
+use crate::social::{self, Social, SocialTable};
+use crate::help::{HelpLookup, HelpTable};
+use crate::hedit::{Hedit, HeditResult};
+use crate::config::Config;
+use crate::ban::{BanList, BanType, MultiplayExemptions};
+use crate::copyover::{self, CopyoverState, SavedConnection};
+use crate::telnet::TELOPT_ECHO;
+use crate::gmcp;
+use crate::msdp::{self, MsdpValue};
+use crate::telnet::{TelnetEvent, TELOPT_MSDP};
 use std::collections::HashMap;
+use std::os::fd::RawFd;
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::mpsc;
-use tokio::time::{interval, Duration};
+use tokio::time::{interval, Duration, Instant};
 use anyhow::Result;
 use log::{info, warn, error};
 
@@ -22,10 +35,42 @@ use log::{info, warn, error};
 const CORPSE_NPC_TIMER: i32 = 5;
 const CORPSE_PC_TIMER: i32 = 60;
 
+/// Where idle players are parked until they type something (Limbo). See
+/// check_idling in /web/deltamud/src/limits.c.
+const IDLE_VOID_ROOM: RoomVnum = 1;
+
+/// A player whose socket dropped mid-game. The body stays in the world,
+/// fights and all, until they log back in or the timeout runs out.
+struct LinkDead {
+    character: Arc<RwLock<Character>>,
+    since: Instant,
+}
+
 pub struct Game {
     world: Arc<RwLock<World>>,
     database: Arc<dyn DatabaseInterface>,
     connections: HashMap<u64, Connection>,
+    link_dead: HashMap<u64, LinkDead>,
+    linkdead_timeout: Duration,
+    idle_void_timeout: Duration,
+    idle_rent_timeout: Duration,
+    login_timeout: Duration,
+    lib_path: String,
+    use_mock_db: bool,
+    /// The telnet listener, handed to the new process on copyover
+    listener_fd: Option<RawFd>,
+    /// Site bans, shared with the accept loops in main
+    bans: Arc<RwLock<BanList>>,
+    /// Sites where several characters may play at once
+    multiplay_ok: MultiplayExemptions,
+    /// Socials from lib/misc/socials, tried when no command matches
+    socials: SocialTable,
+    /// Help entries from lib/text/help
+    help: HelpTable,
+    /// Players with an alias save under way, and the newest set waiting
+    /// to be written after it
+    alias_saves: Arc<Mutex<HashMap<u64, Option<Vec<Alias>>>>>,
+    max_conns_per_ip: usize,
     next_conn_id: u64,
     violence_timer: u64,
     zone_age_tick: u64,
@@ -33,11 +78,30 @@ pub struct Game {
 }
 
 impl Game {
-    pub fn new(world: Arc<RwLock<World>>, database: Arc<dyn DatabaseInterface>) -> Self {
+    pub fn new(
+        world: Arc<RwLock<World>>,
+        database: Arc<dyn DatabaseInterface>,
+        bans: Arc<RwLock<BanList>>,
+        config: &Config,
+    ) -> Self {
         Game {
             world,
             database,
             connections: HashMap::new(),
+            link_dead: HashMap::new(),
+            linkdead_timeout: Duration::from_secs(config.linkdead_timeout),
+            idle_void_timeout: Duration::from_secs(config.idle_void_timeout),
+            idle_rent_timeout: Duration::from_secs(config.idle_rent_timeout),
+            login_timeout: Duration::from_secs(config.login_timeout),
+            lib_path: config.lib_path.clone(),
+            use_mock_db: config.use_mock_db,
+            listener_fd: None,
+            bans,
+            multiplay_ok: MultiplayExemptions::load(&config.lib_path),
+            socials: SocialTable::load(&config.lib_path),
+            help: HelpTable::load(&config.lib_path),
+            alias_saves: Arc::new(Mutex::new(HashMap::new())),
+            max_conns_per_ip: config.max_conns_per_ip,
             next_conn_id: 1,
             violence_timer: 0,
             zone_age_tick: 0,
@@ -59,6 +123,21 @@ impl Game {
         }
     }
 
+    pub fn set_listener_fd(&mut self, fd: RawFd) {
+        self.listener_fd = Some(fd);
+    }
+
+    /// Carry zone ages over from before a copyover, so resets keep their
+    /// schedule instead of all restarting from zero.
+    pub fn restore_zone_ages(&mut self, ages: &[(i32, i32)]) {
+        let mut world = self.world.write();
+        for (number, age) in ages {
+            if let Some(zone) = world.zones.iter_mut().find(|z| z.number == *number) {
+                zone.age = *age;
+            }
+        }
+    }
+
     /// Run an initial reset pass on every zone so the world is populated
     /// before any player connects. Matches CircleMUD boot behaviour (see
     /// /web/deltamud/src/db.c reset_time_zones).
@@ -86,23 +165,69 @@ impl Game {
         Ok(())
     }
 
-    async fn act_to_room(
-        &self,
-        room: &Arc<RwLock<Room>>,
-        exclude_ch_id: u64,
-        msg: &str,
-    ) -> Result<()> {
-        let recipients: Vec<u64> = {
-            let room = room.read();
-            room.people
-                .iter()
-                .filter_map(|w| w.upgrade())
-                .map(|ch| ch.read().id)
-                .filter(|id| *id != exclude_ch_id)
-                .collect()
-        };
-        for id in recipients {
-            self.send_to_char(id, msg).await?;
+    async fn send_gmcp_to_char(&self, ch_id: u64, package: &str, data: &serde_json::Value) -> Result<()> {
+        for conn in self.connections.values() {
+            if let Some(conn_ch) = &conn.character {
+                if conn_ch.read().id == ch_id {
+                    conn.send_gmcp(package, data).await?;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Current MSDP variables for a connection's character, including the
+    /// zone name that only the world knows.
+    fn msdp_variables(world: &RwLock<World>, conn: &Connection) -> Vec<(&'static str, MsdpValue)> {
+        match &conn.character {
+            Some(ch) => {
+                let ch = ch.read();
+                let zone = ch.in_room.as_ref()
+                    .and_then(|w| w.upgrade())
+                    .map(|r| r.read().zone);
+                let zone_name = zone.and_then(|zn| {
+                    world.read().zones.iter().find(|z| z.number == zn).map(|z| z.name.clone())
+                });
+                msdp::variables(Some(&ch), zone_name.as_deref())
+            }
+            None => msdp::variables(None, None),
+        }
+    }
+
+    /// Push changed GMCP vitals/status and reported MSDP variables for one
+    /// connection.
+    async fn update_oob(&mut self, conn_id: u64) -> Result<()> {
+        if let Some(conn) = self.connections.get_mut(&conn_id) {
+            conn.update_gmcp_vitals().await?;
+            if conn.telnet.local_enabled(TELOPT_MSDP) {
+                let vars = Self::msdp_variables(&self.world, conn);
+                conn.report_msdp(&vars).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Push out-of-band updates to every playing connection. Called after
+    /// combat rounds and regeneration, which change points without the
+    /// player typing anything.
+    async fn update_all_oob(&mut self) -> Result<()> {
+        let playing: Vec<u64> = self.connections.iter()
+            .filter(|(_, c)| c.state == ConnectionState::Playing)
+            .map(|(id, _)| *id)
+            .collect();
+        for conn_id in playing {
+            self.update_oob(conn_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Deliver act() messages, each rendered for the people it reaches.
+    pub(crate) async fn act(&self, acts: impl IntoIterator<Item = Act>) -> Result<()> {
+        for act in acts {
+            for (id, msg) in act.render() {
+                self.send_to_char(id, &msg).await?;
+            }
         }
         Ok(())
     }
@@ -130,66 +255,282 @@ impl Game {
     
     async fn handle_message(&mut self, msg: GameMessage) -> Result<()> {
         match msg {
-            GameMessage::NewConnection { id, addr, output_tx } => {
-                info!("New connection from {}", addr);
-                let conn = Connection::new(id, addr, output_tx);
+            GameMessage::NewConnection { id, addr, output_tx, transport, fd } => {
+                info!("New {:?} connection from {}", transport, addr);
+                let mut conn = Connection::new(id, addr, output_tx, transport);
+                conn.fd = fd;
+                
+                let open = self.connections.values().filter(|c| c.addr.ip() == addr.ip()).count();
+                if self.max_conns_per_ip > 0
+                    && open >= self.max_conns_per_ip
+                    && !self.multiplay_ok.is_exempt(addr.ip())
+                {
+                    warn!("Refusing connection from {}: {} already open", addr.ip(), open);
+                    conn.send_line("Sorry, there are too many connections from your site right now.").await?;
+                    conn.close().await?;
+                    return Ok(());
+                }
+                conn.start_negotiation().await?;
                 conn.send_prompt().await?;
                 self.connections.insert(id, conn);
             }
             
+            GameMessage::Recovered { id, output_tx, saved, elapsed } => {
+                self.recover_connection(id, output_tx, *saved, elapsed).await?;
+            }
+            
             GameMessage::Input { conn_id, input } => {
-                if let Some(_conn) = self.connections.get_mut(&conn_id) {
-                    self.handle_input(conn_id, input).await?;
+                if let Some(conn) = self.connections.get_mut(&conn_id) {
+                    conn.queue_input(input).await?;
+                    self.run_queued_input(conn_id).await?;
+                }
+            }
+            
+            GameMessage::Telnet { conn_id, event } => {
+                if let Some(conn) = self.connections.get_mut(&conn_id) {
+                    match event {
+                        TelnetEvent::Subnegotiation(TELOPT_MSDP, payload) => {
+                            let vars = Self::msdp_variables(&self.world, conn);
+                            conn.handle_msdp(&payload, &vars).await?;
+                        }
+                        event => conn.handle_telnet(event).await?,
+                    }
                 }
             }
             
             GameMessage::Disconnect { conn_id } => {
                 info!("Connection {} disconnected", conn_id);
                 
-                // Extract character ID first
-                let char_to_save = self.connections.get(&conn_id)
-                    .and_then(|conn| conn.character.as_ref())
-                    .map(|ch| ch.clone());
+                let conn = match self.connections.remove(&conn_id) {
+                    Some(conn) => conn,
+                    None => return Ok(()),
+                };
+                let ch = match conn.character {
+                    Some(ch) => ch,
+                    None => return Ok(()),
+                };
                 
-                // Remove connection
-                if let Some(conn) = self.connections.remove(&conn_id) {
-                    if let Some(ch) = &conn.character {
-                        let ch_id = ch.read().id;
-                        self.world.write().remove_character(ch_id);
-                    }
+                if conn.state == ConnectionState::Playing && !ch.read().is_npc {
+                    // Leave the body in the world without a descriptor, as
+                    // CircleMUD's close_socket does, so a dropped link
+                    // doesn't cost the player their fight or their corpse.
+                    let ch_id = ch.read().id;
+                    info!("{} has lost link", ch.read().get_name());
+                    self.act([Act::new("$n has lost $s link.", &ch, TO_ROOM).hide_invisible()]).await?;
+                    self.save_character(&ch);
+                    self.link_dead.insert(ch_id, LinkDead { character: ch, since: Instant::now() });
+                } else {
+                    let ch_id = ch.read().id;
+                    self.world.write().remove_character(ch_id);
+                    self.save_character(&ch);
                 }
-                
-                // Save character after connection is removed
-                if let Some(ch) = char_to_save {
-                    // Snapshot character state (stripping !Send Weak refs) before spawning.
-                    let (ch_for_save, is_npc, ch_id, name) = {
-                        let ch_guard = ch.read();
-                        (
-                            ch_guard.clone_for_save(),
-                            ch_guard.is_npc,
-                            ch_guard.id,
-                            ch_guard.get_name().to_string(),
-                        )
-                    };
+            }
+        }
+        Ok(())
+    }
 
-                    if !is_npc && ch_id > 0 {
-                        let db = self.database.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = db.save_player(&ch_for_save).await {
-                                warn!("Failed to save player {}: {}", name, e);
-                            } else {
-                                info!("Saved player {} on disconnect", name);
-                            }
-                        });
-                    }
+    /// Save a player in the background. NPCs and characters that never
+    /// made it into the database are skipped.
+    fn save_character(&self, ch: &Arc<RwLock<Character>>) {
+        // Snapshot character state (stripping !Send Weak refs) before spawning.
+        let (ch_for_save, is_npc, ch_id, name) = {
+            let ch_guard = ch.read();
+            (
+                ch_guard.clone_for_save(),
+                ch_guard.is_npc,
+                ch_guard.id,
+                ch_guard.get_name().to_string(),
+            )
+        };
+
+        if !is_npc && ch_id > 0 {
+            let db = self.database.clone();
+            tokio::spawn(async move {
+                if let Err(e) = db.save_player(&ch_for_save).await {
+                    warn!("Failed to save player {}: {}", name, e);
+                } else {
+                    info!("Saved player {}", name);
                 }
+            });
+        }
+    }
+
+    /// Fill in a freshly loaded player's aliases. They're a convenience,
+    /// so failing to read them doesn't stop the login.
+    async fn load_aliases(database: &Arc<dyn DatabaseInterface>, ch: &mut Character) {
+        match database.load_aliases(ch.id).await {
+            Ok(aliases) => ch.aliases = aliases,
+            Err(e) => warn!("Failed to load aliases for {}: {}", ch.get_name(), e),
+        }
+    }
+
+    /// Save a player and wait for it, for when the process is about to go
+    /// away and a background save would never finish.
+    async fn save_character_now(&self, ch: &Arc<RwLock<Character>>) {
+        let (ch_for_save, is_npc, ch_id, name) = {
+            let ch_guard = ch.read();
+            (
+                ch_guard.clone_for_save(),
+                ch_guard.is_npc,
+                ch_guard.id,
+                ch_guard.get_name().to_string(),
+            )
+        };
+        if !is_npc && ch_id > 0 {
+            if let Err(e) = self.database.save_player(&ch_for_save).await {
+                warn!("Failed to save player {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Find a link-dead body by player name.
+    fn find_link_dead(&self, name: &str) -> Option<u64> {
+        self.link_dead.iter()
+            .find(|(_, ld)| ld.character.read().get_name().eq_ignore_ascii_case(name))
+            .map(|(ch_id, _)| *ch_id)
+    }
+
+    /// Put a reconnecting player back into their link-dead body.
+    async fn reconnect(&mut self, conn_id: u64, ch_id: u64) -> Result<()> {
+        let ld = match self.link_dead.remove(&ch_id) {
+            Some(ld) => ld,
+            None => return Ok(()),
+        };
+        let ch = ld.character;
+        let name = ch.read().get_name().to_string();
+        if let Some(conn) = self.connections.get_mut(&conn_id) {
+            ch.write().player.host = conn.addr.ip().to_canonical().to_string();
+            conn.character = Some(ch.clone());
+            conn.temp_name = None;
+            conn.state = ConnectionState::Playing;
+            conn.send_line("Reconnecting.").await?;
+        }
+        info!("{} has reconnected", name);
+        self.check_multiplaying(conn_id);
+        self.act([Act::new("$n has reconnected.", &ch, TO_ROOM).hide_invisible()]).await?;
+        self.do_look(conn_id, "".to_string()).await
+    }
+
+    /// Warn when a character enters the game from an address that already
+    /// has someone playing, unless the site is exempt (check_multiplaying
+    /// in /web/deltamud/src/comm.c, which refused the login outright).
+    fn check_multiplaying(&self, conn_id: u64) {
+        let conn = match self.connections.get(&conn_id) {
+            Some(conn) => conn,
+            None => return,
+        };
+        let ip = conn.addr.ip();
+        if self.multiplay_ok.is_exempt(ip) {
+            return;
+        }
+        let others: Vec<String> = self.connections.values()
+            .filter(|c| c.id != conn_id && c.state == ConnectionState::Playing && c.addr.ip() == ip)
+            .filter_map(|c| c.character.as_ref().map(|ch| ch.read().get_name().to_string()))
+            .collect();
+        if others.is_empty() {
+            return;
+        }
+        let name = conn.character.as_ref()
+            .map(|ch| ch.read().get_name().to_string())
+            .unwrap_or_default();
+        warn!("Possible multiplaying from {}: {} joins {}", ip, name, others.join(", "));
+    }
+
+    /// Extract link-dead characters whose timeout has run out.
+    fn expire_link_dead(&mut self) {
+        let expired: Vec<u64> = self.link_dead.iter()
+            .filter(|(_, ld)| ld.since.elapsed() >= self.linkdead_timeout)
+            .map(|(ch_id, _)| *ch_id)
+            .collect();
+        for ch_id in expired {
+            if let Some(ld) = self.link_dead.remove(&ch_id) {
+                info!("Extracting link-dead {}", ld.character.read().get_name());
+                self.save_character(&ld.character);
+                self.world.write().remove_character(ch_id);
             }
         }
-        Ok(())
     }
     
-    async fn handle_input(&mut self, conn_id: u64, input: String) -> Result<()> {
+    /// Run the connection's next queued line, if it's allowed one now.
+    async fn run_queued_input(&mut self, conn_id: u64) -> Result<()> {
+        let (line, aliased) = match self.connections.get_mut(&conn_id).and_then(|c| c.next_input()) {
+            Some(next) => next,
+            None => return Ok(()),
+        };
+        self.handle_input(conn_id, line, aliased).await
+    }
+
+    /// Resolve !, !prefix and ^old^new against the connection's history.
+    /// None means there was nothing to expand to, and the player has been
+    /// told.
+    async fn expand_history(&mut self, conn_id: u64, input: String) -> Result<Option<String>> {
+        let conn = match self.connections.get_mut(&conn_id) {
+            Some(conn) => conn,
+            None => return Ok(None),
+        };
+        match conn.history.expand(&input) {
+            Ok(line) => Ok(Some(line)),
+            Err(message) => {
+                conn.send_line(message).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Expand `input` if it starts with one of the character's aliases
+    /// (perform_alias in /web/deltamud/src/interpreter.c). Returns the
+    /// line to run now; the rest of a complex alias waits at the front of
+    /// the input queue.
+    fn perform_alias(&mut self, conn_id: u64, input: String) -> String {
+        let conn = match self.connections.get_mut(&conn_id) {
+            Some(conn) => conn,
+            None => return input,
+        };
+        let lines = match &conn.character {
+            Some(ch) => {
+                let ch = ch.read();
+                let (word, args) = interpreter::split_command(&input);
+                match alias::find(&ch.aliases, &word) {
+                    Some(alias) => alias.expand(args),
+                    None => return input,
+                }
+            }
+            None => return input,
+        };
+
+        let mut lines = lines.into_iter();
+        let first = lines.next().unwrap_or_default();
+        conn.queue_alias_lines(lines.collect());
+        first
+    }
+
+    /// Count down wait states and run whatever input they were holding
+    /// back, one line per connection per pulse.
+    async fn process_input_queues(&mut self) -> Result<()> {
+        let ids: Vec<u64> = self.connections.keys().copied().collect();
+        for id in ids {
+            if let Some(conn) = self.connections.get_mut(&id) {
+                conn.pulse_wait();
+                self.run_queued_input(id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_input(&mut self, conn_id: u64, input: String, aliased: bool) -> Result<()> {
         let conn = self.connections.get_mut(&conn_id).unwrap();
+        conn.last_input = Instant::now();
+
+        // The entry editor keeps a line's indentation; everything else
+        // reads it trimmed.
+        let raw = input;
+        let input = raw.trim().to_string();
+
+        // With echo suppressed the client doesn't echo the Enter key either,
+        // so move off the password prompt line ourselves.
+        if conn.telnet.local_enabled(TELOPT_ECHO) {
+            conn.send("\r\n").await?;
+        }
         
         match conn.state {
             ConnectionState::GetName => {
@@ -219,14 +560,36 @@ impl Game {
             ConnectionState::Menu => {
                 self.handle_menu(conn_id, input).await?;
             }
+            ConnectionState::Playing if conn.pager.is_some() => {
+                conn.show_page(&input).await?;
+            }
+            ConnectionState::Playing if conn.hedit.is_some() => {
+                self.hedit_parse(conn_id, &raw).await?;
+            }
             ConnectionState::Playing => {
-                self.handle_command(conn_id, input).await?;
+                self.return_from_void(conn_id).await?;
+                // Lines an alias produced aren't expanded again, so an
+                // alias can't recurse. History comes first so ! can repeat
+                // an alias.
+                let line = if aliased {
+                    Some(input)
+                } else {
+                    self.expand_history(conn_id, input).await?
+                        .map(|line| self.perform_alias(conn_id, line))
+                };
+                if let Some(line) = line {
+                    self.handle_command(conn_id, line).await?;
+                }
             }
             _ => {}
         }
         
-        // Send next prompt
-        if let Some(conn) = self.connections.get(&conn_id) {
+        // Send next prompt, hiding input first if it asks for a password
+        if self.connections.get(&conn_id).map(|c| c.state) == Some(ConnectionState::Playing) {
+            self.update_oob(conn_id).await?;
+        }
+        if let Some(conn) = self.connections.get_mut(&conn_id) {
+            conn.update_echo().await?;
             conn.send_prompt().await?;
         }
         
@@ -234,15 +597,26 @@ impl Game {
     }
     
     async fn handle_get_name(&mut self, conn_id: u64, input: String) -> Result<()> {
+        let name = input.trim().to_string();
+        
+        // A link-dead body can be reclaimed with the right password, so it
+        // doesn't count as already playing.
+        let already_playing = self.find_link_dead(&name).is_none()
+            && self.world.read().find_character_by_name(&name).is_some();
+        
         let conn = self.connections.get_mut(&conn_id).unwrap();
         
         if input.is_empty() {
-            conn.close();
+            conn.close().await?;
             return Ok(());
         }
         
-        let name = input.trim().to_string();
-        
+        // Letters only, as in _parse_name in /web/deltamud/src/interpreter.c
+        if !name.chars().all(|c| c.is_ascii_alphabetic()) {
+            conn.send_line("Invalid name, please try another.").await?;
+            return Ok(());
+        }
+
         // Check if name is valid
         if name.len() < 3 || name.len() > 12 {
             conn.send_line("Names must be 3-12 characters long.").await?;
@@ -250,7 +624,7 @@ impl Game {
         }
         
         // Check if already playing
-        if self.world.read().find_character_by_name(&name).is_some() {
+        if already_playing {
             conn.send_line("That character is already playing!").await?;
             return Ok(());
         }
@@ -258,6 +632,13 @@ impl Game {
         // Check database for existing player
         let exists = self.database.player_exists(&name).await?;
         
+        if !exists && self.bans.read().is_banned(conn.addr.ip()) >= BanType::New {
+            info!("Request for new char {} denied from [{}] (siteban)", name, conn.addr.ip());
+            conn.send_line("Sorry, new characters are not allowed from your site!").await?;
+            conn.close().await?;
+            return Ok(());
+        }
+        
         conn.temp_name = Some(name);
         
         if exists {
@@ -270,8 +651,9 @@ impl Game {
     }
     
     async fn handle_get_password(&mut self, conn_id: u64, input: String) -> Result<()> {
+        let name = self.connections[&conn_id].temp_name.clone().unwrap();
+        let link_dead = self.find_link_dead(&name);
         let conn = self.connections.get_mut(&conn_id).unwrap();
-        let name = conn.temp_name.clone().unwrap();
         
         // Verify password
         let valid = self.database.verify_password(&name, &input).await?;
@@ -284,9 +666,25 @@ impl Game {
             return Ok(());
         }
         
+        // A SELECT ban only lets in characters cleared with SITEOK, and
+        // that holds for taking over a link-dead body too
+        let select_banned = self.bans.read().is_banned(conn.addr.ip()) == BanType::Select;
+        if let Some(ch_id) = link_dead {
+            let site_ok = self.link_dead.get(&ch_id)
+                .is_some_and(|ld| ld.character.read().act_flags & PLR_SITEOK != 0);
+            if select_banned && !site_ok {
+                return Self::refuse_site(conn, &name).await;
+            }
+            return self.reconnect(conn_id, ch_id).await;
+        }
+        
         // Load character from database
         match self.database.load_player(&name).await {
-            Ok(character) => {
+            Ok(character) if select_banned && character.act_flags & PLR_SITEOK == 0 => {
+                Self::refuse_site(conn, &name).await?;
+            }
+            Ok(mut character) => {
+                Self::load_aliases(&self.database, &mut character).await;
                 let ch_arc = self.world.write().create_character(character);
                 conn.character = Some(ch_arc);
                 conn.state = ConnectionState::Menu;
@@ -302,6 +700,13 @@ impl Game {
         Ok(())
     }
     
+    /// Turn away a character not cleared for login from a SELECT-banned site.
+    async fn refuse_site(conn: &mut Connection, name: &str) -> Result<()> {
+        info!("Connection attempt for {} denied from {}", name, conn.addr.ip());
+        conn.send_line("Sorry, this char has not been cleared for login from your site!").await?;
+        conn.close().await
+    }
+
     async fn handle_new_password(&mut self, conn_id: u64, input: String) -> Result<()> {
         let conn = self.connections.get_mut(&conn_id).unwrap();
         
@@ -418,7 +823,7 @@ impl Game {
         match input.chars().next() {
             Some('0') => {
                 conn.send_line("Goodbye!").await?;
-                conn.close();
+                conn.close().await?;
             }
             Some('1') => {
                 // Enter game
@@ -444,20 +849,7 @@ impl Game {
 
                     let preferred = ch.read().player.hometown;
                     let ch_name_snapshot = ch.read().get_name().to_string();
-                    let start_room = {
-                        let world = self.world.read();
-                        if world.get_room(preferred).is_some() {
-                            preferred
-                        } else {
-                            // Fallback: lowest loaded room >= 100 (zone 0
-                            // is reserved for Limbo/utility in CircleMUD).
-                            // Fall all the way back to any room if nothing
-                            // higher than Limbo loaded.
-                            world.rooms.keys().filter(|v| **v >= 100).min().copied()
-                                .or_else(|| world.rooms.keys().min().copied())
-                                .unwrap_or(0)
-                        }
-                    };
+                    let start_room = Self::entry_room(&self.world.read(), preferred);
                     let move_err = {
                         self.world.read().move_character(ch.clone(), start_room)
                             .err()
@@ -469,8 +861,10 @@ impl Game {
                         return Ok(());
                     }
 
+                    ch.write().player.host = conn.addr.ip().to_canonical().to_string();
                     conn.send_line("\r\n&YWelcome to DeltaMUD!&n\r\n").await?;
                     conn.state = ConnectionState::Playing;
+                    self.check_multiplaying(conn_id);
 
                     // Look at room
                     self.do_look(conn_id, "".to_string()).await?;
@@ -484,131 +878,106 @@ impl Game {
         Ok(())
     }
     
+    /// The room a player enters the game in: `preferred` if it exists,
+    /// otherwise the lowest loaded room >= 100 (zone 0 is reserved for
+    /// Limbo/utility in CircleMUD), otherwise any room at all.
+    fn entry_room(world: &World, preferred: RoomVnum) -> RoomVnum {
+        if world.get_room(preferred).is_some() {
+            preferred
+        } else {
+            world.rooms.keys().filter(|v| **v >= 100).min().copied()
+                .or_else(|| world.rooms.keys().min().copied())
+                .unwrap_or(0)
+        }
+    }
+    
+    /// Look up and run a command (command_interpreter in
+    /// /web/deltamud/src/interpreter.c).
     async fn handle_command(&mut self, conn_id: u64, input: String) -> Result<()> {
-        let parts: Vec<&str> = input.split_whitespace().collect();
-        if parts.is_empty() {
+        let (word, args) = interpreter::split_command(&input);
+        if word.is_empty() {
             return Ok(());
         }
         
-        let command = parts[0].to_lowercase();
-        let args = parts[1..].join(" ");
-        
-        // Get character for command execution
-        let messages = if let Some(conn) = self.connections.get(&conn_id) {
-            if let Some(ch) = &conn.character {
-                let world = self.world.read();
-                
-                match command.as_str() {
-                    // Movement
-                    "look" | "l" => {
-                        drop(world);
-                        self.do_look(conn_id, args).await?;
-                        return Ok(());
-                    }
-                    "north" | "n" => {
-                        drop(world);
-                        self.do_move(conn_id, NORTH).await?;
-                        return Ok(());
-                    }
-                    "east" | "e" => {
-                        drop(world);
-                        self.do_move(conn_id, EAST).await?;
-                        return Ok(());
-                    }
-                    "south" | "s" => {
-                        drop(world);
-                        self.do_move(conn_id, SOUTH).await?;
-                        return Ok(());
-                    }
-                    "west" | "w" => {
-                        drop(world);
-                        self.do_move(conn_id, WEST).await?;
-                        return Ok(());
-                    }
-                    "up" | "u" => {
-                        drop(world);
-                        self.do_move(conn_id, UP).await?;
-                        return Ok(());
-                    }
-                    "down" | "d" => {
-                        drop(world);
-                        self.do_move(conn_id, DOWN).await?;
-                        return Ok(());
-                    }
-                    
-                    // Communication
-                    "say" => {
-                        drop(world);
-                        self.do_say(conn_id, args).await?;
-                        return Ok(());
-                    }
-                    "tell" => {
-                        drop(world);
-                        self.do_tell(conn_id, args).await?;
-                        return Ok(());
-                    }
-                    "shout" => {
-                        drop(world);
-                        self.do_shout(conn_id, args).await?;
-                        return Ok(());
-                    }
-                    
-                    // Information
-                    "who" => Commands::do_who(&ch.read(), &world, &args),
-                    "score" | "sc" => Commands::do_score(&ch.read(), &world, &args),
-                    "inventory" | "inv" | "i" => Commands::do_inventory(&ch.read(), &world, &args),
-                    "equipment" | "eq" => Commands::do_equipment(&ch.read(), &world, &args),
-                    
-                    // Objects
-                    "get" | "take" => Commands::do_get(&mut ch.write(), &world, &args),
-                    "drop" => Commands::do_drop(&mut ch.write(), &world, &args),
-                    "wear" => Commands::do_wear(&mut ch.write(), &world, &args),
-                    "remove" => Commands::do_remove(&mut ch.write(), &world, &args),
-                    
-                    // Combat
-                    "kill" | "k" | "hit" => {
-                        drop(world);
-                        Commands::do_kill(ch.clone(), &self.world.read(), &args)
-                    }
-                    "flee" => {
-                        drop(world);
-                        self.do_flee(conn_id).await?;
-                        return Ok(());
-                    }
-                    
-                    // Magic
-                    "cast" | "c" => {
-                        drop(world);
-                        Commands::do_cast(ch.clone(), &self.world.read(), &args)
-                    }
-                    
-                    // System
-                    "quit" => {
-                        drop(world);
-                        self.do_quit(conn_id).await?;
-                        return Ok(());
-                    }
-                    
-                    _ => vec!["Huh?!?".to_string()],
-                }
-            } else {
-                vec!["You must be logged in to use commands.".to_string()]
+        let conn = match self.connections.get(&conn_id) {
+            Some(conn) => conn,
+            None => return Ok(()),
+        };
+        let (level, position, is_npc) = match &conn.character {
+            Some(ch) => {
+                let ch = ch.read();
+                (ch.player.level, ch.position, ch.is_npc)
             }
-        } else {
-            vec![]
+            None => return conn.send_line("You must be logged in to use commands.").await,
         };
         
-        // Send messages to player
-        if let Some(conn) = self.connections.get(&conn_id) {
-            for msg in messages {
-                conn.send_line(&msg).await?;
+        let cmd = match interpreter::find_command(&word, level) {
+            Some(cmd) => cmd,
+            None => {
+                // Socials rank after every regular command
+                // (create_command_list in /web/deltamud/src/act.social.c)
+                let social = match self.socials.find(&word, level) {
+                    Some(social) => social.clone(),
+                    None => return conn.send_line("Huh?!?").await,
+                };
+                if position < social.min_char_position {
+                    return conn.send_line(interpreter::position_message(position)).await;
+                }
+                return self.do_action(conn_id, &social, args).await;
             }
+        };
+        if is_npc && !cmd.npc_ok {
+            return conn.send_line("You can't do that while switched.").await;
+        }
+        if position < cmd.min_position {
+            return conn.send_line(interpreter::position_message(position)).await;
         }
         
-        Ok(())
+        (cmd.handler)(self, conn_id, args, cmd.subcmd).await
+    }
+    
+    /// Run a command that reports back as lines of text, and send them
+    /// to the player, paged if they run long.
+    pub(crate) async fn run_messages<F>(&mut self, conn_id: u64, command: F) -> Result<()>
+    where
+        F: FnOnce(&Arc<RwLock<Character>>, &World) -> Vec<String>,
+    {
+        let conn = match self.connections.get_mut(&conn_id) {
+            Some(conn) => conn,
+            None => return Ok(()),
+        };
+        let messages = match &conn.character {
+            Some(ch) => command(ch, &self.world.read()),
+            None => return Ok(()),
+        };
+        if messages.is_empty() {
+            return Ok(());
+        }
+        let mut text = messages.join("\r\n");
+        text.push_str("\r\n");
+        conn.page_string(&text).await
     }
     
-    async fn do_look(&mut self, conn_id: u64, _args: String) -> Result<()> {
+    /// Run a command that reports back through act(), and deliver its
+    /// messages to everyone they reach.
+    pub(crate) async fn run_acts<F>(&mut self, conn_id: u64, command: F) -> Result<()>
+    where
+        F: FnOnce(&Arc<RwLock<Character>>, &World) -> Vec<Act>,
+    {
+        let ch = match self.connections.get(&conn_id).and_then(|c| c.character.clone()) {
+            Some(ch) => ch,
+            None => return Ok(()),
+        };
+        let acts = command(&ch, &self.world.read());
+        self.act(acts).await
+    }
+
+    pub(crate) async fn do_look(&mut self, conn_id: u64, args: String) -> Result<()> {
+        let (word, rest) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+        if interpreter::is_abbrev(word, "in") {
+            let arg = rest.trim().to_string();
+            return self.run_messages(conn_id, |ch, world| Commands::look_in_obj(&ch.read(), world, &arg)).await;
+        }
         let conn = self.connections.get(&conn_id).unwrap();
         
         // Collect all the room data first, then send messages
@@ -620,22 +989,18 @@ impl Game {
                     let ch_id = ch.id;
                     
                     // Collect room info
+                    let zone_name = self.world.read().zones.iter()
+                        .find(|z| z.number == room.zone)
+                        .map(|z| z.name.clone());
+                    let room_gmcp = gmcp::room_info(&room, zone_name.as_deref());
                     let room_name = room.name.clone();
                     let room_desc = room.description.clone();
                     
                     // Collect exits
                     let mut exits = Vec::new();
                     for (dir, exit) in room.exits.iter().enumerate() {
-                        if exit.is_some() {
-                            exits.push(match dir {
-                                0 => "north",
-                                1 => "east", 
-                                2 => "south",
-                                3 => "west",
-                                4 => "up",
-                                5 => "down",
-                                _ => "unknown",
-                            });
+                        if exit.as_ref().is_some_and(|e| !e.is_closed()) {
+                            exits.push(DIRS[dir]);
                         }
                     }
                     
@@ -663,7 +1028,7 @@ impl Game {
                         objects.push(label);
                     }
 
-                    Some((room_name, room_desc, exits, people, objects))
+                    Some((room_name, room_desc, exits, people, objects, room_gmcp))
                 } else {
                     None
                 }
@@ -675,7 +1040,9 @@ impl Game {
         };
         
         // Now send messages without holding any locks
-        if let Some((room_name, room_desc, exits, people, objects)) = room_data {
+        if let Some((room_name, room_desc, exits, people, objects, room_gmcp)) = room_data {
+            conn.send_gmcp("Room.Info", &room_gmcp).await?;
+
             // Room name
             conn.send_line(&format!("&c{}&n", room_name)).await?;
 
@@ -702,7 +1069,7 @@ impl Game {
         Ok(())
     }
     
-    async fn do_say(&mut self, conn_id: u64, args: String) -> Result<()> {
+    pub(crate) async fn do_say(&mut self, conn_id: u64, args: String) -> Result<()> {
         if args.is_empty() {
             if let Some(conn) = self.connections.get(&conn_id) {
                 conn.send_line("Say what?").await?;
@@ -710,28 +1077,37 @@ impl Game {
             return Ok(());
         }
 
-        let (ch_id, ch_name, room) = match self.connections.get(&conn_id).and_then(|c| c.character.as_ref()) {
+        let (ch, ch_id, ch_name, room) = match self.connections.get(&conn_id).and_then(|c| c.character.as_ref()) {
             Some(ch) => {
                 let ch_read = ch.read();
                 let room = ch_read.in_room.as_ref().and_then(|w| w.upgrade());
-                (ch_read.id, ch_read.get_name().to_string(), room)
+                (ch.clone(), ch_read.id, ch_read.get_name().to_string(), room)
             }
             None => return Ok(()),
         };
 
+        let channel = gmcp::comm_channel("say", &ch_name, &args);
         if let Some(conn) = self.connections.get(&conn_id) {
             conn.send_line(&format!("You say, '{}'", args)).await?;
+            conn.send_gmcp("Comm.Channel.Text", &channel).await?;
         }
 
         if let Some(room) = room {
-            let msg = format!("{} says, '{}'", ch_name, args);
-            self.act_to_room(&room, ch_id, &msg).await?;
+            self.act([Act::new("$n says, '$T'", &ch, TO_ROOM).text(args.as_str())]).await?;
+            let listeners: Vec<u64> = room.read().people.iter()
+                .filter_map(|w| w.upgrade())
+                .map(|p| p.read().id)
+                .filter(|id| *id != ch_id)
+                .collect();
+            for id in listeners {
+                self.send_gmcp_to_char(id, "Comm.Channel.Text", &channel).await?;
+            }
         }
 
         Ok(())
     }
 
-    async fn do_tell(&mut self, conn_id: u64, args: String) -> Result<()> {
+    pub(crate) async fn do_tell(&mut self, conn_id: u64, args: String) -> Result<()> {
         let mut parts = args.splitn(2, char::is_whitespace);
         let target_name = parts.next().unwrap_or("").trim().to_string();
         let message = parts.next().unwrap_or("").trim().to_string();
@@ -762,10 +1138,13 @@ impl Game {
                     return Ok(());
                 }
                 let display_name = target_ch.read().get_name().to_string();
+                let channel = gmcp::comm_channel("tell", &speaker_name, &message);
                 if let Some(conn) = self.connections.get(&conn_id) {
                     conn.send_line(&format!("You tell {}, '{}'", display_name, message)).await?;
+                    conn.send_gmcp("Comm.Channel.Text", &channel).await?;
                 }
                 self.send_to_char(target_id, &format!("{} tells you, '{}'", speaker_name, message)).await?;
+                self.send_gmcp_to_char(target_id, "Comm.Channel.Text", &channel).await?;
             }
             None => {
                 if let Some(conn) = self.connections.get(&conn_id) {
@@ -776,7 +1155,7 @@ impl Game {
         Ok(())
     }
 
-    async fn do_shout(&mut self, conn_id: u64, args: String) -> Result<()> {
+    pub(crate) async fn do_shout(&mut self, conn_id: u64, args: String) -> Result<()> {
         if args.trim().is_empty() {
             if let Some(conn) = self.connections.get(&conn_id) {
                 conn.send_line("Shout what?").await?;
@@ -792,8 +1171,10 @@ impl Game {
             None => return Ok(()),
         };
 
+        let channel = gmcp::comm_channel("shout", &speaker_name, &args);
         if let Some(conn) = self.connections.get(&conn_id) {
             conn.send_line(&format!("You shout, '{}'", args)).await?;
+            conn.send_gmcp("Comm.Channel.Text", &channel).await?;
         }
 
         let recipients: Vec<u64> = self.connections.values()
@@ -805,11 +1186,12 @@ impl Game {
         let msg = format!("{} shouts, '{}'", speaker_name, args);
         for id in recipients {
             self.send_to_char(id, &msg).await?;
+            self.send_gmcp_to_char(id, "Comm.Channel.Text", &channel).await?;
         }
         Ok(())
     }
 
-    async fn do_move(&mut self, conn_id: u64, direction: usize) -> Result<()> {
+    pub(crate) async fn do_move(&mut self, conn_id: u64, direction: usize) -> Result<()> {
         let dir_name = match direction {
             NORTH => "north",
             EAST => "east",
@@ -829,7 +1211,7 @@ impl Game {
             _ => "nowhere",
         };
 
-        let (ch_arc, ch_id, ch_name, old_room) = {
+        let (ch_arc, old_room) = {
             let conn = match self.connections.get(&conn_id) {
                 Some(c) => c,
                 None => return Ok(()),
@@ -838,25 +1220,22 @@ impl Game {
                 Some(ch) => ch.clone(),
                 None => return Ok(()),
             };
-            let (id, name, old_room) = {
-                let ch_read = ch.read();
-                let old_room = ch_read.in_room.as_ref().and_then(|w| w.upgrade());
-                (ch_read.id, ch_read.get_name().to_string(), old_room)
-            };
-            (ch, id, name, old_room)
+            let old_room = ch.read().in_room.as_ref().and_then(|w| w.upgrade());
+            (ch, old_room)
         };
 
-        let to_room_vnum = match &old_room {
-            Some(room) => match room.read().get_exit(direction) {
-                Some(exit) => Some(exit.to_room),
-                None => None,
-            },
-            None => None,
-        };
+        let exit = old_room.as_ref().and_then(|room| room.read().get_exit(direction).cloned());
 
-        let to_room_vnum = match to_room_vnum {
-            Some(v) => v,
-            None => {
+        let to_room_vnum = match exit {
+            // A hidden door that's shut looks like no exit at all
+            Some(exit) if exit.is_closed() && !exit.is_hidden() => {
+                if let Some(conn) = self.connections.get(&conn_id) {
+                    conn.send_line(&format!("The {} seems to be closed.", exit.door_name())).await?;
+                }
+                return Ok(());
+            }
+            Some(exit) if !exit.is_closed() => exit.to_room,
+            _ => {
                 if let Some(conn) = self.connections.get(&conn_id) {
                     conn.send_line("You can't go that way.").await?;
                 }
@@ -874,10 +1253,8 @@ impl Game {
             return Ok(());
         }
 
-        if let Some(room) = &old_room {
-            let msg = format!("{} leaves {}.", ch_name, dir_name);
-            self.act_to_room(room, ch_id, &msg).await?;
-        }
+        let leave = format!("$n leaves {}.", dir_name);
+        self.act([Act::new(leave, &ch_arc, TO_ROOM).hide_invisible()]).await?;
 
         let move_err = self.world.read()
             .move_character(ch_arc.clone(), to_room_vnum)
@@ -891,17 +1268,14 @@ impl Game {
             return Ok(());
         }
 
-        let new_room = ch_arc.read().in_room.as_ref().and_then(|w| w.upgrade());
-        if let Some(room) = &new_room {
-            let msg = format!("{} arrives from the {}.", ch_name, opposite_dir);
-            self.act_to_room(room, ch_id, &msg).await?;
-        }
+        let arrive = format!("$n arrives from the {}.", opposite_dir);
+        self.act([Act::new(arrive, &ch_arc, TO_ROOM).hide_invisible()]).await?;
 
         self.do_look(conn_id, String::new()).await?;
         Ok(())
     }
 
-    async fn do_flee(&mut self, conn_id: u64) -> Result<()> {
+    pub(crate) async fn do_flee(&mut self, conn_id: u64) -> Result<()> {
         use rand::seq::SliceRandom;
 
         let (ch_arc, fighting, exits) = match self.connections.get(&conn_id).and_then(|c| c.character.as_ref()) {
@@ -913,7 +1287,7 @@ impl Game {
                     .map(|room| {
                         let room = room.read();
                         (0..room.exits.len())
-                            .filter(|i| room.exits[*i].is_some())
+                            .filter(|i| room.exits[*i].as_ref().is_some_and(|e| !e.is_closed()))
                             .collect()
                     })
                     .unwrap_or_default();
@@ -947,15 +1321,578 @@ impl Game {
         Ok(())
     }
 
-    async fn do_quit(&mut self, conn_id: u64) -> Result<()> {
+    pub(crate) async fn do_quit(&mut self, conn_id: u64) -> Result<()> {
         if let Some(conn) = self.connections.get_mut(&conn_id) {
             conn.send_line("Goodbye!").await?;
-            conn.close();
+            conn.close().await?;
         }
         Ok(())
     }
     
+    /// Perform a social (do_action in /web/deltamud/src/act.social.c).
+    pub(crate) async fn do_action(&mut self, conn_id: u64, social: &Social, args: &str) -> Result<()> {
+        let ch = match self.connections.get(&conn_id).and_then(|c| c.character.clone()) {
+            Some(ch) => ch,
+            None => return Ok(()),
+        };
+        let room = match ch.read().in_room.as_ref().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return Ok(()),
+        };
+
+        let messages = {
+            let (others, room_objects) = {
+                let room = room.read();
+                let others: Vec<_> = room.people.iter()
+                    .filter_map(|w| w.upgrade())
+                    .filter(|p| !Arc::ptr_eq(p, &ch))
+                    .collect();
+                (others, room.contents.clone())
+            };
+            let ch = ch.read();
+            let others: Vec<_> = others.iter().map(|p| p.read()).collect();
+            let objects: Vec<_> = ch.carrying.iter().chain(room_objects.iter()).map(|o| o.read()).collect();
+            let people: Vec<&Character> = others.iter().map(|p| &**p).collect();
+            let objects: Vec<&Object> = objects.iter().map(|o| &**o).collect();
+            social::perform(social, &ch, &people, &objects, args)
+        };
+
+        for (id, message) in messages {
+            self.send_to_char(id, &message).await?;
+        }
+        Ok(())
+    }
+
+    /// Show the help entry for a keyword, or the help screen without one
+    /// (do_help in /web/deltamud/src/act.informative.c).
+    pub(crate) async fn do_help(&mut self, conn_id: u64, args: &str) -> Result<()> {
+        let (conn, ch) = match self.connections.get_mut(&conn_id) {
+            Some(conn) => match conn.character.clone() {
+                Some(ch) => (conn, ch),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        let args = args.trim();
+        if args.is_empty() {
+            return conn.page_string(&self.help.screen).await;
+        }
+        if self.help.entries.is_empty() {
+            return conn.send_line("No help available.").await;
+        }
+
+        let (name, level) = {
+            let ch = ch.read();
+            (ch.get_name().to_string(), ch.player.level)
+        };
+        match self.help.find(args, level) {
+            HelpLookup::Found(entry) => {
+                let text = format!("{}\r\n{}", entry.keywords, entry.entry);
+                conn.page_string(&text).await
+            }
+            HelpLookup::NotFound(suggestions) => {
+                info!("HELP: {} tried to get help on {}", name, args);
+                let mut text = String::from("There is no help on that word.\r\n");
+                if !suggestions.is_empty() {
+                    text.push_str("Did you mean:\r\n");
+                    for keyword in suggestions {
+                        text.push_str(&format!("  {}\r\n", keyword));
+                    }
+                }
+                conn.page_string(&text).await
+            }
+        }
+    }
+
+    /// Open the help editor on an entry, or on a new one if no entry has
+    /// that keyword (do_olc with SCMD_OLC_HEDIT).
+    pub(crate) async fn do_hedit(&mut self, conn_id: u64, args: &str) -> Result<()> {
+        let keyword = args.trim().trim_matches('"');
+        if keyword.is_empty() {
+            if let Some(conn) = self.connections.get(&conn_id) {
+                conn.send_line("Specify a help topic to edit.").await?;
+            }
+            return Ok(());
+        }
+
+        let mut hedit = match self.help.find_exact(keyword) {
+            Some(rnum) => Hedit::existing(self.help.entries[rnum].clone(), self.help.files.clone()),
+            None => Hedit::new_entry(keyword, self.help.files.clone()),
+        };
+        let editor = self.connections.values()
+            .filter(|c| c.id != conn_id)
+            .find(|c| c.hedit.as_ref().is_some_and(|h| h.original.is_some() && h.original == hedit.original))
+            .and_then(|c| c.character.as_ref().map(|ch| ch.read().get_name().to_string()));
+        let conn = match self.connections.get_mut(&conn_id) {
+            Some(conn) => conn,
+            None => return Ok(()),
+        };
+        if let Some(editor) = editor {
+            return conn.send_line(&format!("That help entry is currently being edited by {}.", editor)).await;
+        }
+        let menu = hedit.menu();
+        conn.hedit = Some(hedit);
+        conn.send(&menu).await
+    }
+
+    /// A line typed while in the help editor.
+    async fn hedit_parse(&mut self, conn_id: u64, input: &str) -> Result<()> {
+        let conn = match self.connections.get_mut(&conn_id) {
+            Some(conn) => conn,
+            None => return Ok(()),
+        };
+        let result = match conn.hedit.as_mut() {
+            Some(hedit) => hedit.parse(input),
+            None => return Ok(()),
+        };
+        let hedit = match result {
+            HeditResult::Continue(text) => return conn.send(&text).await,
+            HeditResult::Quit => {
+                conn.hedit = None;
+                return Ok(());
+            }
+            HeditResult::Save | HeditResult::Delete => match conn.hedit.take() {
+                Some(hedit) => hedit,
+                None => return Ok(()),
+            },
+        };
+        let name = conn.character.as_ref().map(|ch| ch.read().get_name().to_string()).unwrap_or_default();
+        let delete = matches!(result, HeditResult::Delete);
+
+        // Put the change into the table, then write out the file or files
+        // it touches and reload so every lookup sees the new text
+        let found = hedit.original.as_ref().and_then(|(file, keywords)| {
+            self.help.entries.iter().position(|e| e.file == *file && e.keywords == *keywords)
+        });
+        let mut files = vec![hedit.help.file.clone()];
+        if let Some((file, _)) = &hedit.original {
+            if *file != hedit.help.file {
+                files.push(file.clone());
+            }
+        }
+        // A file that didn't load whole would lose what couldn't be read
+        if let Some(file) = files.iter().find(|f| !self.help.loaded_cleanly(f)) {
+            error!("OLC: Not saving help file {}: it didn't load cleanly", file);
+            if let Some(conn) = self.connections.get(&conn_id) {
+                let msg = format!("Help file {} didn't load cleanly, so it can't be saved. Nothing was changed.", file);
+                conn.send_line(&msg).await?;
+            }
+            return Ok(());
+        }
+        match (found, delete) {
+            (Some(rnum), true) => {
+                self.help.entries.remove(rnum);
+            }
+            (Some(rnum), false) => self.help.entries[rnum] = hedit.help.clone(),
+            (None, false) => {
+                let at = self.help.entries.iter()
+                    .rposition(|e| e.file == hedit.help.file)
+                    .map_or(self.help.entries.len(), |i| i + 1);
+                self.help.entries.insert(at, hedit.help.clone());
+            }
+            (None, true) => {}
+        }
+
+        let mut saved = true;
+        for file in &files {
+            if let Err(e) = self.help.save_file(file) {
+                error!("OLC: Cannot save help file {}: {:#}", file, e);
+                saved = false;
+            }
+        }
+        if saved {
+            self.help = HelpTable::load(&self.lib_path);
+        }
+        info!("OLC: {} {} help for {}.", name, if delete { "deletes" } else { "edits" }, hedit.help.keywords);
+
+        let msg = match (saved, delete) {
+            (false, _) => "Your change is in memory, but the help file could not be written.",
+            (true, true) => "Help entry deleted.",
+            (true, false) => "Help entry saved.",
+        };
+        if let Some(conn) = self.connections.get(&conn_id) {
+            conn.send_line(msg).await?;
+        }
+        Ok(())
+    }
+
+    /// List, add, redefine or delete aliases (do_alias in
+    /// /web/deltamud/src/interpreter.c). Changes are saved right away.
+    pub(crate) async fn do_alias(&mut self, conn_id: u64, args: &str) -> Result<()> {
+        let (conn, ch) = match self.connections.get_mut(&conn_id) {
+            Some(conn) => match conn.character.clone() {
+                Some(ch) => (conn, ch),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        if ch.read().is_npc {
+            return Ok(());
+        }
+
+        let (name, replacement) = match args.split_once(char::is_whitespace) {
+            Some((name, replacement)) => (name.to_lowercase(), replacement.trim()),
+            None => (args.to_lowercase(), ""),
+        };
+
+        if name.is_empty() {
+            let text = {
+                let ch = ch.read();
+                let mut text = String::from("Currently defined aliases:\r\n");
+                if ch.aliases.is_empty() {
+                    text.push_str(" None.\r\n");
+                }
+                for alias in &ch.aliases {
+                    text.push_str(&format!("{:<15} {}\r\n", alias.name, alias.replacement));
+                }
+                text
+            };
+            return conn.page_string(&text).await;
+        }
+
+        if !replacement.is_empty() && name == "alias" {
+            return conn.send_line("You can't alias 'alias'.").await;
+        }
+
+        let (message, changed, player_id, aliases) = {
+            let mut ch = ch.write();
+            let existed = match ch.aliases.iter().position(|a| a.name == name) {
+                Some(pos) => {
+                    ch.aliases.remove(pos);
+                    true
+                }
+                None => false,
+            };
+            let message = if !replacement.is_empty() {
+                ch.aliases.insert(0, Alias::new(&name, replacement));
+                "Alias added."
+            } else if existed {
+                "Alias deleted."
+            } else {
+                "No such alias."
+            };
+            let changed = existed || !replacement.is_empty();
+            (message, changed, ch.id, ch.aliases.clone())
+        };
+        conn.send_line(message).await?;
+
+        if changed && player_id > 0 {
+            self.queue_alias_save(player_id, aliases);
+        }
+        Ok(())
+    }
+
+    /// Save a player's aliases off the game loop. One save runs per
+    /// player at a time; changes made meanwhile wait, and only the newest
+    /// set is written next, so a pasted batch of alias lines can't land
+    /// out of order.
+    fn queue_alias_save(&self, player_id: u64, aliases: Vec<Alias>) {
+        let running = self.alias_saves.lock().insert(player_id, Some(aliases)).is_some();
+        if running {
+            return;
+        }
+        let saves = self.alias_saves.clone();
+        let db = self.database.clone();
+        tokio::spawn(async move {
+            loop {
+                let aliases = {
+                    let mut saves = saves.lock();
+                    match saves.get_mut(&player_id).and_then(Option::take) {
+                        Some(aliases) => aliases,
+                        None => {
+                            saves.remove(&player_id);
+                            break;
+                        }
+                    }
+                };
+                if let Err(e) = db.save_aliases(player_id, &aliases).await {
+                    warn!("Failed to save aliases for player {}: {}", player_id, e);
+                }
+            }
+        });
+    }
+
+    /// List site bans, or add one (do_ban in /web/deltamud/src/ban.c).
+    pub(crate) async fn do_ban(&mut self, conn_id: u64, args: &str) -> Result<()> {
+        let (conn, name) = match self.connections.get_mut(&conn_id) {
+            Some(conn) => match &conn.character {
+                Some(ch) => {
+                    let name = ch.read().get_name().to_string();
+                    (conn, name)
+                }
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        if args.is_empty() {
+            let text = {
+                let bans = self.bans.read();
+                let row = |site: &str, ban_type: &str, date: &str, by: &str| {
+                    format!("{:<25.25}  {:<8.8}  {:<10.10}  {:<16.16}\r\n", site, ban_type, date, by)
+                };
+                let rule = "-".repeat(33);
+                let mut text = row("Banned Site Name", "Ban Type", "Banned On", "Banned By");
+                text.push_str(&row(&rule, &rule, &rule, &rule));
+                for entry in bans.entries() {
+                    text.push_str(&row(&entry.site, entry.ban_type.name(), &entry.date_string(), &entry.name));
+                }
+                (!bans.entries().is_empty()).then_some(text)
+            };
+            return match text {
+                Some(text) => conn.page_string(&text).await,
+                None => conn.send_line("No sites are banned.").await,
+            };
+        }
+
+        let mut parts = args.split_whitespace();
+        let (flag, site) = match (parts.next(), parts.next()) {
+            (Some(flag), Some(site)) => (flag, site),
+            _ => return conn.send_line("Usage: ban {all | select | new} site_name").await,
+        };
+        let ban_type = match BanType::from_name(flag) {
+            Some(ban_type) if ban_type != BanType::Not => ban_type,
+            _ => return conn.send_line("Flag must be ALL, SELECT, or NEW.").await,
+        };
+
+        let result = self.bans.write().ban(site, ban_type, &name);
+        match result {
+            Ok(()) => {
+                info!("{} has banned {} for {} players.", name, site, ban_type.name());
+                conn.send_line("Site banned.").await
+            }
+            Err(msg) => conn.send_line(msg).await,
+        }
+    }
+
+    /// Lift a site ban (do_unban in /web/deltamud/src/ban.c).
+    pub(crate) async fn do_unban(&mut self, conn_id: u64, args: &str) -> Result<()> {
+        let (conn, name) = match self.connections.get_mut(&conn_id) {
+            Some(conn) => match &conn.character {
+                Some(ch) => {
+                    let name = ch.read().get_name().to_string();
+                    (conn, name)
+                }
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        let site = match args.split_whitespace().next() {
+            Some(site) => site,
+            None => return conn.send_line("A site to unban might help.").await,
+        };
+        let removed = self.bans.write().unban(site);
+        match removed {
+            Some(entry) => {
+                info!("{} removed the {}-player ban on {}.", name, entry.ban_type.name(), entry.site);
+                conn.send_line("Site unbanned.").await
+            }
+            None => conn.send_line("That site is not currently banned.").await,
+        }
+    }
+
+    /// List, add or remove sites exempt from multiplaying warnings and
+    /// the per-address connection cap.
+    pub(crate) async fn do_multiok(&mut self, conn_id: u64, args: &str) -> Result<()> {
+        let (conn, name) = match self.connections.get(&conn_id) {
+            Some(conn) => match &conn.character {
+                Some(ch) => {
+                    let name = ch.read().get_name().to_string();
+                    (conn, name)
+                }
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        let mut parts = args.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (None, _) => {
+                if self.multiplay_ok.sites().is_empty() {
+                    return conn.send_line("No sites are cleared for multiplaying.").await;
+                }
+                conn.send_line("Sites cleared for multiplaying:").await?;
+                for site in self.multiplay_ok.sites() {
+                    conn.send_line(&format!("  {}", site)).await?;
+                }
+                Ok(())
+            }
+            (Some("add"), Some(site)) => {
+                if self.multiplay_ok.add(site) {
+                    info!("{} cleared {} for multiplaying", name, site);
+                    conn.send_line("Site cleared.").await
+                } else {
+                    conn.send_line("That site is already cleared.").await
+                }
+            }
+            (Some("remove"), Some(site)) => {
+                if self.multiplay_ok.remove(site) {
+                    info!("{} removed the multiplaying clearance for {}", name, site);
+                    conn.send_line("Site clearance removed.").await
+                } else {
+                    conn.send_line("That site isn't cleared.").await
+                }
+            }
+            _ => conn.send_line("Usage: multiok [add | remove] <site>").await,
+        }
+    }
+
+    /// Hot reboot, after do_copyover in /web/deltamud/src/act.wizard.c.
+    /// Saves every player, hands playing telnet sockets to a fresh copy of
+    /// the binary and execs it. Only returns if something went wrong
+    /// before the exec.
+    pub(crate) async fn do_copyover(&mut self, conn_id: u64) -> Result<()> {
+        let (immortal_name, conn) = match self.connections.get(&conn_id) {
+            Some(conn) => match &conn.character {
+                Some(ch) => (ch.read().get_name().to_string(), conn),
+                None => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        if self.use_mock_db {
+            conn.send_line("Copyover needs a real player database; the mock one would lose everyone.").await?;
+            return Ok(());
+        }
+        let listener_fd = match self.listener_fd.map(copyover::inherit_fd) {
+            Some(Ok(fd)) => fd,
+            Some(Err(e)) => {
+                conn.send_line(&format!("Copyover aborted: {}", e)).await?;
+                return Ok(());
+            }
+            None => {
+                conn.send_line("Copyover aborted: no listening socket to hand over.").await?;
+                return Ok(());
+            }
+        };
+
+        // Dup the sockets being handed over and write the state file before
+        // anyone is told anything, so a failure leaves every player where
+        // they were (C opens COPYOVER_FILE first for the same reason)
+        let mut saved = Vec::new();
+        let mut handed_over = Vec::new();
+        for (&id, conn) in &self.connections {
+            let (ConnectionState::Playing, Some(fd), Some(ch)) = (conn.state, conn.fd, &conn.character) else {
+                continue;
+            };
+            // Put void-pulled players back where they were
+            let room = {
+                let ch = ch.read();
+                ch.was_in_room.as_ref()
+                    .or(ch.in_room.as_ref())
+                    .and_then(|w| w.upgrade())
+                    .map(|r| r.read().number)
+            };
+            let Some(room) = room else { continue };
+            let new_fd = match copyover::inherit_fd(fd) {
+                Ok(new_fd) => new_fd,
+                Err(e) => {
+                    warn!("Copyover: can't hand over connection {}: {}", id, e);
+                    continue;
+                }
+            };
+            match conn.save_for_copyover(new_fd, room) {
+                Some(session) => {
+                    saved.push(session);
+                    handed_over.push(id);
+                }
+                None => copyover::close_fd(new_fd),
+            }
+        }
+        let zone_ages = self.world.read().zones.iter().map(|z| (z.number, z.age)).collect();
+        let state = CopyoverState { listener_fd, connections: saved, zone_ages };
+        if let Err(e) = copyover::save(&self.lib_path, &state) {
+            error!("Copyover aborted: {:#}", e);
+            copyover::release(&state);
+            if let Some(conn) = self.connections.get(&conn_id) {
+                conn.send_line("Copyover file not writeable, aborted.").await?;
+            }
+            return Ok(());
+        }
+
+        info!("Copyover by {}", immortal_name);
+        let announce = format!("\r\nThe server is being rebooted by {}. Please standby..", immortal_name);
+        let conn_ids: Vec<u64> = self.connections.keys().copied().collect();
+        for id in conn_ids {
+            let conn = self.connections.get_mut(&id).unwrap();
+            let ch = conn.character.clone();
+            if handed_over.contains(&id) {
+                conn.send_line(&announce).await?;
+            } else {
+                conn.send_line("\r\nSorry, we are rebooting. Come back in a minute.").await?;
+                conn.close().await?;
+            }
+            if let Some(ch) = ch {
+                self.save_character_now(&ch).await;
+            }
+        }
+        for ld in self.link_dead.values() {
+            self.save_character_now(&ld.character).await;
+        }
+        // Closed connections' writers are gone and would never ack a
+        // flush, so only wait on the sockets being handed over
+        for id in &handed_over {
+            if let Some(conn) = self.connections.get(id) {
+                conn.drain().await?;
+            }
+        }
+
+        // exec only returns on failure, and by now every socket has been
+        // told we're rebooting, so there's no clean way back (as in C).
+        let err = copyover::exec();
+        error!("Copyover exec failed: {}", err);
+        std::process::exit(1);
+    }
+
+    /// Rebuild a session inherited across copyover, after
+    /// copyover_recover in /web/deltamud/src/comm.c.
+    async fn recover_connection(
+        &mut self,
+        id: u64,
+        output_tx: mpsc::Sender<crate::connection::Outbound>,
+        saved: SavedConnection,
+        elapsed: Duration,
+    ) -> Result<()> {
+        let name = saved.name.clone();
+        let room = saved.room;
+        let mut conn = Connection::new(id, saved.addr, output_tx, crate::connection::Transport::Telnet);
+        conn.restore_from_copyover(saved).await?;
+        conn.send_line("\r\nRestoring from copyover...").await?;
+
+        let mut character = match self.database.load_player(&name).await {
+            Ok(character) => character,
+            Err(e) => {
+                error!("Copyover: failed to load {}: {}", name, e);
+                conn.send_line("\r\nSomehow, your character was lost in the copyover. Sorry.").await?;
+                conn.close().await?;
+                self.connections.insert(id, conn);
+                return Ok(());
+            }
+        };
+        Self::load_aliases(&self.database, &mut character).await;
+        let ch = self.world.write().create_character(character);
+        let room = Self::entry_room(&self.world.read(), room);
+        if let Err(e) = self.world.read().move_character(ch.clone(), room) {
+            warn!("Copyover: failed to place {} into room {}: {}", name, room, e);
+        }
+        conn.character = Some(ch);
+        conn.state = ConnectionState::Playing;
+        conn.send_line(&format!(
+            "\r\nThe reboot has been completed in {}.{:03} seconds. You may continue playing.",
+            elapsed.as_secs(),
+            elapsed.subsec_millis()
+        )).await?;
+        self.connections.insert(id, conn);
+        self.do_look(id, "".to_string()).await?;
+        if let Some(conn) = self.connections.get(&id) {
+            conn.send_prompt().await?;
+        }
+        Ok(())
+    }
+
     async fn game_tick(&mut self) -> Result<()> {
+        self.process_input_queues().await?;
+
         self.violence_timer += 1;
         
         // Process combat every PULSE_VIOLENCE ticks
@@ -981,6 +1918,18 @@ impl Game {
             if REGEN_TIMER >= 300 {
                 REGEN_TIMER = 0;
                 self.regenerate_characters();
+                self.update_all_oob().await?;
+            }
+        }
+        
+        // Link-dead bodies and idle connections are checked once a second
+        static mut IDLE_TIMER: u64 = 0;
+        unsafe {
+            IDLE_TIMER += 1;
+            if IDLE_TIMER >= 10 {
+                IDLE_TIMER = 0;
+                self.expire_link_dead();
+                self.check_idling().await?;
             }
         }
         
@@ -997,6 +1946,105 @@ impl Game {
         Ok(())
     }
 
+    /// Time out idle connections, following check_idling in
+    /// /web/deltamud/src/limits.c and check_idle_passwords in comm.c:
+    /// sockets left at the name or password prompt are closed, idle
+    /// players are pulled into the void and, later, saved and disconnected.
+    async fn check_idling(&mut self) -> Result<()> {
+        let conn_ids: Vec<u64> = self.connections.keys().copied().collect();
+        for conn_id in conn_ids {
+            let (state, idle) = {
+                let conn = &self.connections[&conn_id];
+                (conn.state, conn.last_input.elapsed())
+            };
+            match state {
+                ConnectionState::Close => {}
+                ConnectionState::GetName
+                | ConnectionState::GetOldPassword
+                | ConnectionState::GetNewPassword
+                | ConnectionState::ConfirmPassword
+                    if idle >= self.login_timeout =>
+                {
+                    let conn = self.connections.get_mut(&conn_id).unwrap();
+                    conn.send("\r\nTimed out... goodbye.\r\n").await?;
+                    conn.close().await?;
+                }
+                _ if idle >= self.idle_rent_timeout => {
+                    let conn = self.connections.get_mut(&conn_id).unwrap();
+                    if let Some(ch) = &conn.character {
+                        // The Disconnect that follows saves and extracts
+                        // the character.
+                        ch.write().was_in_room = None;
+                        info!("{} force-rented and extracted (idle).", ch.read().get_name());
+                    }
+                    conn.close().await?;
+                }
+                ConnectionState::Playing if idle >= self.idle_void_timeout => {
+                    self.pull_into_void(conn_id).await?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Park an idle player in the void, remembering where they were.
+    async fn pull_into_void(&mut self, conn_id: u64) -> Result<()> {
+        let ch = match self.connections.get(&conn_id).and_then(|c| c.character.clone()) {
+            Some(ch) => ch,
+            None => return Ok(()),
+        };
+        let room = {
+            let ch = ch.read();
+            if ch.was_in_room.is_some() {
+                return Ok(());
+            }
+            match ch.in_room.as_ref().and_then(|w| w.upgrade()) {
+                Some(room) => room,
+                None => return Ok(()),
+            }
+        };
+        if room.read().number == IDLE_VOID_ROOM {
+            return Ok(());
+        }
+
+        let opponent = ch.read().fighting.as_ref().and_then(|w| w.upgrade());
+        if let Some(opponent) = opponent {
+            Combat::stop_fighting(&mut opponent.write());
+        }
+        Combat::stop_fighting(&mut ch.write());
+
+        self.act([Act::new("$n disappears into the void.", &ch, TO_ROOM).hide_invisible()]).await?;
+        if let Err(e) = self.world.read().move_character(ch.clone(), IDLE_VOID_ROOM) {
+            warn!("Cannot pull {} into the void: {}", ch.read().get_name(), e);
+            return Ok(());
+        }
+        ch.write().was_in_room = Some(Arc::downgrade(&room));
+        if let Some(conn) = self.connections.get(&conn_id) {
+            conn.send_line("You have been idle, and are pulled into a void.").await?;
+        }
+        self.save_character(&ch);
+        Ok(())
+    }
+
+    /// Bring a player who was pulled into the void back where they were.
+    async fn return_from_void(&mut self, conn_id: u64) -> Result<()> {
+        let ch = match self.connections.get(&conn_id).and_then(|c| c.character.clone()) {
+            Some(ch) => ch,
+            None => return Ok(()),
+        };
+        let room = match ch.write().was_in_room.take().and_then(|w| w.upgrade()) {
+            Some(room) => room,
+            None => return Ok(()),
+        };
+        let vnum = room.read().number;
+        if let Err(e) = self.world.read().move_character(ch.clone(), vnum) {
+            warn!("Cannot return {} from the void: {}", ch.read().get_name(), e);
+            return Ok(());
+        }
+        self.act([Act::new("$n has returned.", &ch, TO_ROOM).hide_invisible()]).await
+    }
+
     fn tick_zone_ages(&mut self) {
         // Reset mode:
         //   0 = never reset
@@ -1063,13 +2111,13 @@ impl Game {
         // per-attacker messages and any death events to process after release.
         let (combat_messages, deaths) = {
             let world = self.world.read();
-            let mut combat_messages: Vec<(Arc<RwLock<Character>>, Vec<String>)> = Vec::new();
+            let mut combat_messages: Vec<Act> = Vec::new();
             let mut deaths: Vec<DeathResult> = Vec::new();
 
             for (_, ch) in &world.characters {
                 if ch.read().fighting.is_some() {
                     let (messages, death) = Combat::perform_violence(ch.clone());
-                    combat_messages.push((ch.clone(), messages));
+                    combat_messages.extend(messages);
                     if let Some(d) = death {
                         deaths.push(d);
                     }
@@ -1080,12 +2128,7 @@ impl Game {
         };
 
         // Phase 2: deliver combat round messages.
-        for (ch, messages) in combat_messages {
-            let ch_id = ch.read().id;
-            for msg in messages {
-                self.send_to_char(ch_id, &msg).await?;
-            }
-        }
+        self.act(combat_messages).await?;
 
         // Phase 3: process death events (needs world write lock for corpse
         // objects and character extraction).
@@ -1093,6 +2136,7 @@ impl Game {
             self.handle_death(event).await?;
         }
 
+        self.update_all_oob().await?;
         Ok(())
     }
 
@@ -1105,8 +2149,7 @@ impl Game {
         };
 
         // Broadcast the death so everyone in the room sees it.
-        let death_msg = format!("{} is dead! R.I.P.", victim_name);
-        self.act_to_room(&room, u64::MAX, &death_msg).await?;
+        self.act([Act::new("$n is dead!  R.I.P.", &victim, TO_ROOM)]).await?;
 
         // Build the corpse object and move the victim's carried/worn items
         // into it. Mirrors /web/deltamud/src/fight.c:287-347 (make_corpse).
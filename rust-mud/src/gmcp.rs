@@ -0,0 +1,112 @@
+// GMCP (Generic MUD Communication Protocol, telnet option 201)
+//
+// Structured JSON pushed alongside the text stream so client-side gauges
+// and mappers don't have to screen-scrape the prompt. Each message is
+// IAC SB GMCP "<Package.Name> <json>" IAC SE. Package names follow the
+// IRE/Mudlet conventions so stock client scripts work unchanged.
+
+use crate::character::Character;
+use crate::room::Room;
+use crate::telnet::{self, TELOPT_GMCP};
+use crate::types::NUM_OF_DIRS;
+use serde_json::{json, Map, Value};
+
+/// Short direction names used as keys in Room.Info exits.
+const DIR_ABBREVS: [&str; NUM_OF_DIRS] = ["n", "e", "s", "w", "u", "d"];
+
+/// Char.Vitals: current and maximum hit, mana and move points.
+pub fn char_vitals(ch: &Character) -> Value {
+    json!({
+        "hp": ch.points.hit,
+        "maxhp": ch.points.max_hit,
+        "mana": ch.points.mana,
+        "maxmana": ch.points.max_mana,
+        "mv": ch.points.move_points,
+        "maxmv": ch.points.max_move,
+    })
+}
+
+/// Char.Status: identity and the slower-changing state shown by `score`.
+pub fn char_status(ch: &Character) -> Value {
+    json!({
+        "name": ch.get_name(),
+        "level": ch.player.level,
+        "class": format!("{:?}", ch.player.class),
+        "race": format!("{:?}", ch.player.race),
+        "position": format!("{:?}", ch.position),
+        "fighting": ch.fighting.is_some(),
+        "gold": ch.points.gold,
+        "exp": ch.points.exp,
+    })
+}
+
+/// Room.Info: vnum, name, zone and exits (direction -> destination vnum).
+pub fn room_info(room: &Room, zone_name: Option<&str>) -> Value {
+    let mut exits = Map::new();
+    for (dir, exit) in room.exits.iter().enumerate() {
+        if let Some(exit) = exit {
+            exits.insert(DIR_ABBREVS[dir].to_string(), json!(exit.to_room));
+        }
+    }
+    json!({
+        "num": room.number,
+        "name": room.name,
+        "zone": room.zone,
+        "area": zone_name.unwrap_or(""),
+        "environment": format!("{:?}", room.sector_type),
+        "exits": exits,
+    })
+}
+
+/// Comm.Channel.Text: one line of say/tell/shout traffic.
+pub fn comm_channel(channel: &str, talker: &str, text: &str) -> Value {
+    json!({
+        "channel": channel,
+        "talker": talker,
+        "text": text,
+    })
+}
+
+/// Frame a package and its data as a complete telnet subnegotiation.
+pub fn encode(package: &str, data: &Value) -> Vec<u8> {
+    let body = format!("{} {}", package, data);
+    telnet::subnegotiation(TELOPT_GMCP, body.as_bytes())
+}
+
+/// Split an incoming GMCP payload into its package name and JSON data.
+/// Data is optional (e.g. "Core.Ping") and malformed JSON is dropped.
+pub fn decode(payload: &[u8]) -> (String, Option<Value>) {
+    let text = String::from_utf8_lossy(payload);
+    let text = text.trim();
+    match text.split_once(char::is_whitespace) {
+        Some((package, data)) => (package.to_string(), serde_json::from_str(data.trim()).ok()),
+        None => (text.to_string(), None),
+    }
+}
+
+/// Whether a package falls under one of the modules the client listed in
+/// Core.Supports ("Char 1", "Room 1", "Comm.Channel 1", ...). A client that
+/// never sent Core.Supports gets everything.
+pub fn is_supported(supports: &[String], package: &str) -> bool {
+    if supports.is_empty() {
+        return true;
+    }
+    let package = package.to_lowercase();
+    supports.iter().any(|module| {
+        package == *module || package.starts_with(&format!("{}.", module))
+    })
+}
+
+/// Module names from a Core.Supports.Set/Add/Remove list, lowercased and
+/// with the version number dropped.
+pub fn module_names(data: &Value) -> Vec<String> {
+    data.as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|entry| entry.as_str())
+                .filter_map(|entry| entry.split_whitespace().next())
+                .map(|name| name.to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,229 @@
+// Help editor
+//
+// Port of hedit.c (Oasis OLC) from /web/deltamud/src. A builder edits a
+// copy of one help entry through a menu: its keywords, its text, the
+// level needed to read it and the .hlp file it's kept in. Quitting after
+// a change asks whether to save; Game then writes the entry's file and
+// reloads the help table. Unlike the C editor, which wrote the whole
+// table into help.hlp, each entry goes back to its own file.
+
+use crate::help::{self, HelpEntry, HELP_FILE};
+use crate::types::Level;
+
+/// Longest keyword line and entry text accepted (MAX_HELP_KEYWORDS and
+/// MAX_HELP_ENTRY).
+const MAX_HELP_KEYWORDS: usize = 75;
+const MAX_HELP_ENTRY: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeditMode {
+    MainMenu,
+    Keywords,
+    Entry,
+    MinLevel,
+    File,
+    ConfirmSave,
+    ConfirmDelete,
+}
+
+/// What the editor wants done after a line of input.
+pub enum HeditResult {
+    /// Keep editing, showing this text
+    Continue(String),
+    /// Save the entry and leave the editor
+    Save,
+    /// Delete the entry and leave the editor
+    Delete,
+    /// Leave without saving
+    Quit,
+}
+
+pub struct Hedit {
+    pub help: HelpEntry,
+    /// The file and keyword line of the entry being edited, which find it
+    /// again on save. None for a new entry.
+    pub original: Option<(String, String)>,
+    /// The .hlp files an entry can be kept in
+    files: Vec<String>,
+    mode: HeditMode,
+    modified: bool,
+    /// The text typed so far in the entry editor
+    text: String,
+}
+
+impl Hedit {
+    /// Edit a copy of an existing entry (hedit_setup_existing).
+    pub fn existing(help: HelpEntry, files: Vec<String>) -> Self {
+        let original = Some((help.file.clone(), help.keywords.clone()));
+        Hedit { help, original, files, mode: HeditMode::MainMenu, modified: false, text: String::new() }
+    }
+
+    /// Start a new entry under `keyword` (hedit_setup_new).
+    pub fn new_entry(keyword: &str, files: Vec<String>) -> Self {
+        let keyword = keyword.trim().to_uppercase();
+        let keywords = if keyword.contains(' ') { format!("\"{}\"", keyword) } else { keyword };
+        let help = HelpEntry {
+            keywords,
+            entry: "This is an unfinished help entry.\r\n".to_string(),
+            min_level: 0,
+            file: HELP_FILE.to_string(),
+            raw: None,
+        };
+        Hedit { help, original: None, files, mode: HeditMode::MainMenu, modified: true, text: String::new() }
+    }
+
+    /// The main menu (hedit_disp_menu).
+    pub fn menu(&mut self) -> String {
+        self.mode = HeditMode::MainMenu;
+        format!(
+            "&g1&n) Keywords    : &y{}&n\r\n\
+             &g2&n) Entry       :\r\n&y{}&n\
+             &g3&n) Min Level   : &c{}&n\r\n\
+             &g4&n) File        : &c{}&n\r\n\
+             &gD&n) Delete this entry\r\n\
+             &gQ&n) Quit\r\n",
+            self.help.keywords, self.help.entry, self.help.min_level, self.help.file,
+        )
+    }
+
+    /// What to show while waiting for the next line.
+    pub fn prompt(&self) -> &'static str {
+        match self.mode {
+            HeditMode::MainMenu => "Enter choice : ",
+            HeditMode::Keywords | HeditMode::MinLevel | HeditMode::Entry => "] ",
+            HeditMode::File => "Enter file number : ",
+            HeditMode::ConfirmSave => "Do you wish to save this help entry? : ",
+            HeditMode::ConfirmDelete => "Are you sure you want to delete this entry? : ",
+        }
+    }
+
+    /// Handle a line typed in the editor (hedit_parse).
+    pub fn parse(&mut self, arg: &str) -> HeditResult {
+        let arg = arg.trim_end();
+        let choice = arg.trim().chars().next().map(|c| c.to_ascii_lowercase());
+
+        match self.mode {
+            HeditMode::ConfirmSave => match choice {
+                Some('y') => HeditResult::Save,
+                Some('n') => HeditResult::Quit,
+                _ => HeditResult::Continue("Invalid choice!\r\n".to_string()),
+            },
+
+            HeditMode::ConfirmDelete => match choice {
+                Some('y') if self.original.is_some() => HeditResult::Delete,
+                // A new entry isn't in any file yet, so just drop it
+                Some('y') => HeditResult::Quit,
+                _ => HeditResult::Continue(self.menu()),
+            },
+
+            HeditMode::MainMenu => match choice {
+                Some('q') if self.modified => {
+                    self.mode = HeditMode::ConfirmSave;
+                    HeditResult::Continue(String::new())
+                }
+                Some('q') => HeditResult::Quit,
+                Some('1') => {
+                    self.mode = HeditMode::Keywords;
+                    HeditResult::Continue("Enter keywords:-\r\n".to_string())
+                }
+                Some('2') => {
+                    self.mode = HeditMode::Entry;
+                    self.text = self.help.entry.clone();
+                    HeditResult::Continue(format!(
+                        "Enter help entry: (/s saves /h for help)\r\n\r\n{}",
+                        self.text
+                    ))
+                }
+                Some('3') => {
+                    self.mode = HeditMode::MinLevel;
+                    HeditResult::Continue("Enter min level:-\r\n".to_string())
+                }
+                Some('4') => {
+                    self.mode = HeditMode::File;
+                    let mut text = String::new();
+                    for (i, file) in self.files.iter().enumerate() {
+                        text.push_str(&format!("&g{}&n) {}\r\n", i + 1, file));
+                    }
+                    HeditResult::Continue(text)
+                }
+                Some('d') => {
+                    self.mode = HeditMode::ConfirmDelete;
+                    HeditResult::Continue(String::new())
+                }
+                _ => HeditResult::Continue(format!("Invalid choice!\r\n{}", self.menu())),
+            },
+
+            HeditMode::Keywords => {
+                let keywords = arg.trim();
+                if keywords.starts_with('$') {
+                    return HeditResult::Continue("Keywords can't start with '$'.\r\nEnter keywords:-\r\n".to_string());
+                }
+                let keywords: String = keywords.chars().take(MAX_HELP_KEYWORDS).collect();
+                self.help.keywords = if keywords.is_empty() { "UNDEFINED".to_string() } else { keywords };
+                self.changed()
+            }
+
+            HeditMode::MinLevel => match arg.trim().parse::<Level>() {
+                Ok(level) if level <= help::max_level() => {
+                    self.help.min_level = level;
+                    self.changed()
+                }
+                _ => HeditResult::Continue("That is not a valid choice!\r\nEnter min level:-\r\n".to_string()),
+            },
+
+            HeditMode::File => match arg.trim().parse::<usize>().ok().and_then(|n| self.files.get(n.wrapping_sub(1))) {
+                Some(file) => {
+                    self.help.file = file.clone();
+                    self.changed()
+                }
+                None => HeditResult::Continue(self.menu()),
+            },
+
+            HeditMode::Entry => self.edit_text(arg),
+        }
+    }
+
+    /// A line typed into the entry editor (string_add in
+    /// /web/deltamud/src/modify.c, without its line-editing commands).
+    fn edit_text(&mut self, line: &str) -> HeditResult {
+        match line.trim() {
+            "/s" => {
+                self.help.entry = std::mem::take(&mut self.text);
+                self.changed()
+            }
+            "/a" => {
+                self.text.clear();
+                HeditResult::Continue(format!("Edit aborted.\r\n{}", self.menu()))
+            }
+            "/c" => {
+                self.text.clear();
+                HeditResult::Continue("Text cleared.\r\n".to_string())
+            }
+            "/l" => HeditResult::Continue(self.text.clone()),
+            "/h" => HeditResult::Continue(
+                "Editor command formats: /<letter>\r\n\r\n\
+                 /a         -  aborts editor\r\n\
+                 /c         -  clears buffer\r\n\
+                 /h         -  list text editor commands\r\n\
+                 /l         -  lists buffer\r\n\
+                 /s         -  saves text\r\n"
+                    .to_string(),
+            ),
+            _ if self.text.len() + line.len() + 2 > MAX_HELP_ENTRY => {
+                HeditResult::Continue("Entry too long - line ignored.\r\n".to_string())
+            }
+            _ => {
+                self.text.push_str(line);
+                self.text.push_str("\r\n");
+                HeditResult::Continue(String::new())
+            }
+        }
+    }
+
+    fn changed(&mut self) -> HeditResult {
+        self.modified = true;
+        // Written out afresh on save, not as it was read
+        self.help.raw = None;
+        HeditResult::Continue(self.menu())
+    }
+}
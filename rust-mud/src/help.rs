@@ -0,0 +1,384 @@
+// Help files
+//
+// Port of the help table from /web/deltamud/src/db.c (load_help) and
+// do_help in act.informative.c. lib/text/help/index names the .hlp files,
+// ending with '$'. Each file holds entries of the form
+//
+//   KEYWORD "TWO WORDS" ...
+//   body text
+//   ...
+//   #level
+//
+// and ends with a line holding just '$'. A bare '#' means anyone may read
+// the entry, except in wizhelp.hlp, whose entries are for immortals.
+//
+// Entries keep the text they were read from, so saving a file after one
+// edit rewrites only that entry, whatever line endings or level markers
+// the rest of the file uses.
+
+use crate::types::{Level, LVL_IMMORT};
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const HELP_PREFIX: &str = "text/help";
+pub const HELP_INDEX: &str = "text/help/index";
+pub const HELP_PAGE_FILE: &str = "text/help/screen";
+
+/// Where new entries go unless the editor picks another file (HELP_FILE).
+pub const HELP_FILE: &str = "help.hlp";
+
+/// The file whose entries default to immortals only.
+const WIZHELP_FILE: &str = "wizhelp.hlp";
+
+/// DeltaMUD's first immortal level, which the help files use. Ours is
+/// LVL_IMMORT, so levels from here up are shifted down onto our scale.
+const FILE_LVL_IMMORT: i32 = 101;
+const FILE_LVL_IMPL: i32 = 105;
+
+/// How many near misses "did you mean" offers.
+const MAX_SUGGESTIONS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct HelpEntry {
+    /// The keyword line as written, quotes and all
+    pub keywords: String,
+    pub entry: String,
+    pub min_level: Level,
+    /// The .hlp file the entry is kept in
+    pub file: String,
+    /// The entry as it was read, blank lines before it included. None
+    /// once edited, when it's written out afresh.
+    pub raw: Option<String>,
+}
+
+impl HelpEntry {
+    /// The keywords, lowercased, with quoted phrases kept together.
+    pub fn keyword_list(&self) -> Vec<String> {
+        split_keywords(&self.keywords)
+    }
+}
+
+/// How a .hlp file was written, so a save can match it.
+#[derive(Debug, Clone, Default)]
+struct FileStyle {
+    crlf: bool,
+    /// Whether entries at the file's default level end "#0" rather than "#"
+    zero_marker: bool,
+    /// The closing '$' line and anything after it
+    trailer: String,
+    /// Read whole, with every entry closed. Saving a file that wasn't
+    /// would drop whatever couldn't be read.
+    clean: bool,
+}
+
+/// What a help lookup found.
+pub enum HelpLookup<'a> {
+    Found(&'a HelpEntry),
+    /// Nothing matched; these keywords come close
+    NotFound(Vec<String>),
+}
+
+#[derive(Default)]
+pub struct HelpTable {
+    pub entries: Vec<HelpEntry>,
+    /// The .hlp files, in index order
+    pub files: Vec<String>,
+    /// The page shown for HELP on its own
+    pub screen: String,
+    /// Levels of the wizhelp.hlp entries, by keyword line. help.hlp
+    /// carries level 0 copies of them, so these hold in any file.
+    wizhelp_levels: Vec<(String, Level)>,
+    styles: HashMap<String, FileStyle>,
+    lib_path: PathBuf,
+}
+
+impl HelpTable {
+    /// Read the files lib/text/help/index lists, and the help screen. A
+    /// missing or damaged file is skipped rather than stopping the boot.
+    pub fn load(lib_path: &str) -> Self {
+        let lib = Path::new(lib_path);
+        let mut table = HelpTable { lib_path: lib.to_path_buf(), ..HelpTable::default() };
+        table.screen = match std::fs::read_to_string(lib.join(HELP_PAGE_FILE)) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Can't read help screen {}: {}", lib.join(HELP_PAGE_FILE).display(), e);
+                String::new()
+            }
+        };
+
+        let index = match std::fs::read_to_string(lib.join(HELP_INDEX)) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Can't open help index {}: {}", lib.join(HELP_INDEX).display(), e);
+                return table;
+            }
+        };
+
+        for file in index_files(&index) {
+            table.files.push(file.to_string());
+            let path = lib.join(HELP_PREFIX).join(file);
+            let style = match std::fs::read_to_string(&path) {
+                Ok(text) => {
+                    let (entries, style) = parse_help(&text, file);
+                    table.entries.extend(entries);
+                    style
+                }
+                Err(e) => {
+                    warn!("Can't open help file {}: {}", path.display(), e);
+                    FileStyle::default()
+                }
+            };
+            table.styles.insert(file.to_string(), style);
+        }
+        table.wizhelp_levels = table.entries.iter()
+            .filter(|e| e.file == WIZHELP_FILE)
+            .map(|e| (e.keywords.clone(), e.min_level))
+            .collect();
+        info!("Loaded {} help entries.", table.entries.len());
+        table
+    }
+
+    /// The level needed to read `entry`.
+    pub fn level_of(&self, entry: &HelpEntry) -> Level {
+        match self.wizhelp_levels.iter().find(|(keywords, _)| *keywords == entry.keywords) {
+            Some((_, level)) => entry.min_level.max(*level),
+            None => entry.min_level,
+        }
+    }
+
+    /// The entry with `keyword` as one of its keywords, exactly.
+    pub fn find_exact(&self, keyword: &str) -> Option<usize> {
+        let keyword = keyword.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        self.entries.iter().position(|e| e.keyword_list().contains(&keyword))
+    }
+
+    /// Whether `file` was read whole, so saving it can't lose entries.
+    pub fn loaded_cleanly(&self, file: &str) -> bool {
+        self.styles.get(file).is_some_and(|style| style.clean)
+    }
+
+    /// Write one .hlp file back out from the table. Untouched entries go
+    /// back exactly as they were read; edited ones follow the file's line
+    /// endings and level markers. The new text goes to a temporary file
+    /// that then replaces the old one, so a crash midway leaves the old
+    /// file whole rather than a truncated one.
+    pub fn save_file(&self, file: &str) -> Result<()> {
+        let style = match self.styles.get(file) {
+            Some(style) if style.clean => style,
+            _ => return Err(anyhow!("{} didn't load cleanly, so saving it would lose entries", file)),
+        };
+        let path = self.lib_path.join(HELP_PREFIX).join(file);
+        let temp = path.with_extension("hlp.new");
+        let default_level = default_level(file);
+        let eol = if style.crlf { "\r\n" } else { "\n" };
+
+        let mut text = String::new();
+        for entry in self.entries.iter().filter(|e| e.file == file) {
+            if let Some(raw) = &entry.raw {
+                text.push_str(raw);
+                continue;
+            }
+            text.push_str(&entry.keywords);
+            text.push_str(eol);
+            for line in entry.entry.lines() {
+                // A line starting with '#' would end the entry early
+                if line.starts_with('#') {
+                    text.push(' ');
+                }
+                text.push_str(line);
+                text.push_str(eol);
+            }
+            if entry.min_level != default_level {
+                text.push_str(&format!("#{}", level_to_file(entry.min_level)));
+            } else if style.zero_marker {
+                text.push_str("#0");
+            } else {
+                text.push('#');
+            }
+            text.push_str(eol);
+        }
+        if style.trailer.is_empty() {
+            text.push('$');
+            text.push_str(eol);
+        } else {
+            text.push_str(&style.trailer);
+        }
+
+        let mut out = std::fs::File::create(&temp).with_context(|| format!("creating {}", temp.display()))?;
+        out.write_all(text.as_bytes()).with_context(|| format!("writing {}", temp.display()))?;
+        out.sync_all().with_context(|| format!("syncing {}", temp.display()))?;
+        std::fs::rename(&temp, &path).with_context(|| format!("replacing {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Look `argument` up among the entries a character of `level` may
+    /// read. An exact keyword wins; otherwise the first keyword each word
+    /// of the argument abbreviates in turn, so "mag mis" finds "MAGIC
+    /// MISSILE".
+    pub fn find(&self, argument: &str, level: Level) -> HelpLookup<'_> {
+        let words: Vec<String> = argument
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c| c == '"' || c == '\'').to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.is_empty() {
+            return HelpLookup::NotFound(Vec::new());
+        }
+        let wanted = words.join(" ");
+        let visible = || self.entries.iter().filter(|e| self.level_of(e) <= level);
+
+        if let Some(entry) = visible().find(|e| e.keyword_list().contains(&wanted)) {
+            return HelpLookup::Found(entry);
+        }
+        let abbreviates = |keyword: &str| {
+            let parts: Vec<&str> = keyword.split_whitespace().collect();
+            words.len() <= parts.len() && words.iter().zip(&parts).all(|(w, p)| p.starts_with(w.as_str()))
+        };
+        if let Some(entry) = visible().find(|e| e.keyword_list().iter().any(|k| abbreviates(k))) {
+            return HelpLookup::Found(entry);
+        }
+
+        // Offer the keywords closest to what was typed
+        let mut close: Vec<(usize, String)> = Vec::new();
+        for keyword in visible().flat_map(|e| e.keyword_list()) {
+            let distance = edit_distance(&wanted, &keyword);
+            let near = distance <= (wanted.len() / 4).max(1) || (wanted.len() >= 3 && keyword.contains(&wanted));
+            if near && !close.iter().any(|(_, k)| *k == keyword) {
+                close.push((distance, keyword));
+            }
+        }
+        close.sort();
+        close.truncate(MAX_SUGGESTIONS);
+        HelpLookup::NotFound(close.into_iter().map(|(_, k)| k.to_uppercase()).collect())
+    }
+}
+
+/// The file names in the index, up to the closing '$'.
+fn index_files(index: &str) -> impl Iterator<Item = &str> {
+    index.split_whitespace().take_while(|name| !name.starts_with('$'))
+}
+
+/// Read the entries in one .hlp file (load_help), and how it's written.
+fn parse_help(text: &str, file: &str) -> (Vec<HelpEntry>, FileStyle) {
+    let mut entries = Vec::new();
+    let mut lines = text.split_inclusive('\n');
+    let strip = |line: &str| line.trim_end_matches('\n').trim_end_matches('\r').to_string();
+    let default_level = default_level(file);
+    let mut style = FileStyle { crlf: text.lines().next().is_some_and(|l| l.ends_with('\r')), clean: true, ..FileStyle::default() };
+    let mut marker_seen = false;
+    let mut raw = String::new();
+
+    while let Some(raw_line) = lines.next() {
+        let keywords = strip(raw_line);
+        if keywords.starts_with('$') {
+            style.trailer = format!("{}{}", raw_line, lines.collect::<String>());
+            break;
+        }
+        raw.push_str(raw_line);
+        if keywords.trim().is_empty() {
+            continue;
+        }
+        let mut entry = String::new();
+        let mut end = None;
+        for raw_line in lines.by_ref() {
+            raw.push_str(raw_line);
+            let line = strip(raw_line);
+            if let Some(level) = line.strip_prefix('#') {
+                end = Some(level.trim().to_string());
+                break;
+            }
+            entry.push_str(&line);
+            entry.push_str("\r\n");
+        }
+        let Some(level) = end else {
+            warn!("Help entry '{}' in {} has no closing '#'", keywords, file);
+            style.clean = false;
+            break;
+        };
+        let min_level = match level.parse::<i32>() {
+            Ok(level) if level > 0 => level_from_file(level),
+            _ => default_level,
+        };
+        if !marker_seen && min_level == default_level {
+            marker_seen = true;
+            style.zero_marker = level == "0";
+        }
+        // Files saved by the old editor pad entries with blank lines
+        let entry = format!("{}\r\n", entry.trim_end());
+        entries.push(HelpEntry {
+            keywords: keywords.trim().to_string(),
+            entry,
+            min_level,
+            file: file.to_string(),
+            raw: Some(std::mem::take(&mut raw)),
+        });
+    }
+    (entries, style)
+}
+
+/// The level a bare '#' gives entries in `file`.
+fn default_level(file: &str) -> Level {
+    if file == WIZHELP_FILE { LVL_IMMORT } else { 0 }
+}
+
+/// Our level for a level written in a help file.
+fn level_from_file(level: i32) -> Level {
+    let level = if level >= FILE_LVL_IMMORT {
+        LVL_IMMORT as i32 + (level - FILE_LVL_IMMORT)
+    } else {
+        level
+    };
+    level.clamp(0, Level::MAX as i32) as Level
+}
+
+/// The level to write in a help file for one of ours.
+fn level_to_file(level: Level) -> i32 {
+    if level >= LVL_IMMORT {
+        FILE_LVL_IMMORT + (level - LVL_IMMORT) as i32
+    } else {
+        level as i32
+    }
+}
+
+/// The highest level a help entry can ask for (LVL_IMPL).
+pub fn max_level() -> Level {
+    level_from_file(FILE_LVL_IMPL)
+}
+
+/// Split a keyword line into lowercased keywords, keeping "quoted
+/// phrases" as one keyword.
+fn split_keywords(line: &str) -> Vec<String> {
+    let mut keywords = Vec::new();
+    let mut rest = line.trim();
+    while !rest.is_empty() {
+        let (keyword, tail) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        let keyword = keyword.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !keyword.is_empty() {
+            keywords.push(keyword.to_lowercase());
+        }
+        rest = tail.trim_start();
+    }
+    keywords
+}
+
+/// Levenshtein distance, for "did you mean".
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,66 @@
+// Command history
+//
+// The C server remembered one line per descriptor so "!" could repeat it
+// and "^old^new" could fix a typo in it (process_input and perform_subst
+// in /web/deltamud/src/comm.c). We keep the last HISTORY_SIZE lines, which
+// also lets "!prefix" pick out the latest command starting with prefix,
+// so "!c" recasts while "!" might only repeat a "look".
+
+use std::collections::VecDeque;
+
+pub const HISTORY_SIZE: usize = 20;
+
+#[derive(Debug, Default)]
+pub struct History {
+    lines: VecDeque<String>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    /// Expand a typed line against the history and remember the result.
+    /// Lines that aren't history references come back unchanged. The
+    /// error is the message for the player when there is nothing to
+    /// expand to.
+    pub fn expand(&mut self, input: &str) -> Result<String, &'static str> {
+        let input = input.trim_start();
+        let line = if let Some(prefix) = input.strip_prefix('!') {
+            let prefix = prefix.trim();
+            self.lines.iter().rev()
+                .find(|line| line.starts_with(prefix))
+                .cloned()
+                .ok_or(if prefix.is_empty() { "No previous command." } else { "No such command in your history." })?
+        } else if let Some(subst) = input.strip_prefix('^') {
+            let last = self.lines.back().ok_or("Invalid substitution.")?;
+            substitute(last, subst).ok_or("Invalid substitution.")?
+        } else {
+            input.to_string()
+        };
+
+        self.remember(&line);
+        Ok(line)
+    }
+
+    fn remember(&mut self, line: &str) {
+        if line.trim().is_empty() || self.lines.back().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.lines.len() >= HISTORY_SIZE {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.to_string());
+    }
+}
+
+/// Replace the first `old` in `orig` with `new`, given "old^new" or
+/// "old^new^" (perform_subst).
+fn substitute(orig: &str, subst: &str) -> Option<String> {
+    let (old, new) = subst.split_once('^')?;
+    let new = new.strip_suffix('^').unwrap_or(new);
+    if old.is_empty() || !orig.contains(old) {
+        return None;
+    }
+    Some(orig.replacen(old, new, 1))
+}
@@ -0,0 +1,237 @@
+// Command interpreter
+//
+// Port of the cmd_info table and command_interpreter from
+// /web/deltamud/src/interpreter.c. Each command is one CommandInfo entry
+// naming its handler, so adding a command means adding a line here rather
+// than another match arm. Lookup is by prefix in table order: the first
+// entry the typed word abbreviates, at least as far as the entry's
+// minimum abbreviation, and that the character's level allows, wins. That
+// makes the order the priority, so "s" is south rather than say or score.
+
+use crate::commands::Commands;
+use crate::game::Game;
+use crate::types::*;
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type CmdFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// A command handler: the game, the connection typing it, everything
+/// after the command word, and the entry's subcommand.
+pub type CmdHandler = for<'a> fn(&'a mut Game, u64, &'a str, i32) -> CmdFuture<'a>;
+
+pub struct CommandInfo {
+    pub name: &'static str,
+    /// The shortest abbreviation that selects this command
+    pub abbrev: &'static str,
+    pub min_position: Position,
+    pub min_level: Level,
+    /// Whether a mob (or an immortal switched into one) may use it
+    pub npc_ok: bool,
+    /// Passed to the handler, so one handler can serve several commands
+    pub subcmd: i32,
+    pub handler: CmdHandler,
+}
+
+/// Wrap a Game method call as a CmdHandler.
+macro_rules! handler {
+    (|$game:ident, $conn_id:ident, $args:ident, $subcmd:ident| $call:expr) => {{
+        #[allow(unused_variables)]
+        fn call<'a>($game: &'a mut Game, $conn_id: u64, $args: &'a str, $subcmd: i32) -> CmdFuture<'a> {
+            Box::pin($call)
+        }
+        call as CmdHandler
+    }};
+}
+
+macro_rules! cmd {
+    ($name:expr, $abbrev:expr, $pos:ident, $level:expr, $npc_ok:expr, $subcmd:expr, $handler:expr) => {
+        CommandInfo {
+            name: $name,
+            abbrev: $abbrev,
+            min_position: Position::$pos,
+            min_level: $level,
+            npc_ok: $npc_ok,
+            subcmd: $subcmd,
+            handler: $handler,
+        }
+    };
+}
+
+pub const SCMD_HIT: i32 = 1;
+
+// do_gen_door subcommands
+pub const SCMD_OPEN: i32 = 0;
+pub const SCMD_CLOSE: i32 = 1;
+pub const SCMD_UNLOCK: i32 = 2;
+pub const SCMD_LOCK: i32 = 3;
+pub const SCMD_PICK: i32 = 4;
+
+/// The master command list. Directions come first so single letters
+/// move; the rest are alphabetical except where a more common command
+/// should win a shared abbreviation.
+pub static CMD_INFO: &[CommandInfo] = &[
+    cmd!("north", "n", Standing, 0, true, NORTH as i32, handler!(|g, id, args, sub| g.do_move(id, sub as usize))),
+    cmd!("east", "e", Standing, 0, true, EAST as i32, handler!(|g, id, args, sub| g.do_move(id, sub as usize))),
+    cmd!("south", "s", Standing, 0, true, SOUTH as i32, handler!(|g, id, args, sub| g.do_move(id, sub as usize))),
+    cmd!("west", "w", Standing, 0, true, WEST as i32, handler!(|g, id, args, sub| g.do_move(id, sub as usize))),
+    cmd!("up", "u", Standing, 0, true, UP as i32, handler!(|g, id, args, sub| g.do_move(id, sub as usize))),
+    cmd!("down", "d", Standing, 0, true, DOWN as i32, handler!(|g, id, args, sub| g.do_move(id, sub as usize))),
+
+    cmd!("'", "'", Resting, 0, true, 0, handler!(|g, id, args, sub| g.do_say(id, args.to_string()))),
+    cmd!("alias", "ali", Dead, 0, true, 0, handler!(|g, id, args, sub| g.do_alias(id, args))),
+    cmd!("ban", "ban", Dead, LVL_IMMORT, false, 0, handler!(|g, id, args, sub| g.do_ban(id, args))),
+    cmd!("cast", "c", Sitting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_acts(id, |ch, world| Commands::do_cast(ch.clone(), world, args))
+    })),
+    cmd!("close", "clos", Sitting, 0, true, SCMD_CLOSE, handler!(|g, id, args, sub| {
+        g.run_acts(id, move |ch, world| Commands::do_gen_door(ch.clone(), world, args, sub))
+    })),
+    cmd!("copyover", "copyover", Dead, LVL_IMMORT, false, 0, handler!(|g, id, args, sub| g.do_copyover(id))),
+    cmd!("drop", "dro", Resting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_drop(&mut ch.write(), world, args))
+    })),
+    cmd!("equipment", "eq", Sleeping, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_equipment(&ch.read(), world, args))
+    })),
+    cmd!("examine", "exam", Sitting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_examine(&ch.read(), world, args))
+    })),
+    cmd!("flee", "fl", Fighting, 0, true, 0, handler!(|g, id, args, sub| g.do_flee(id))),
+    cmd!("get", "g", Resting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_acts(id, |ch, world| Commands::do_get(ch.clone(), world, args))
+    })),
+    cmd!("hedit", "hedit", Dead, LVL_IMMORT, false, 0, handler!(|g, id, args, sub| g.do_hedit(id, args))),
+    cmd!("help", "hel", Dead, 0, true, 0, handler!(|g, id, args, sub| g.do_help(id, args))),
+    cmd!("hit", "h", Fighting, 0, true, SCMD_HIT, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_kill(ch.clone(), world, args))
+    })),
+    cmd!("inventory", "i", Dead, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_inventory(&ch.read(), world, args))
+    })),
+    cmd!("kill", "k", Fighting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_kill(ch.clone(), world, args))
+    })),
+    cmd!("look", "l", Resting, 0, true, 0, handler!(|g, id, args, sub| g.do_look(id, args.to_string()))),
+    cmd!("lock", "lo", Sitting, 0, true, SCMD_LOCK, handler!(|g, id, args, sub| {
+        g.run_acts(id, move |ch, world| Commands::do_gen_door(ch.clone(), world, args, sub))
+    })),
+    cmd!("multiok", "multiok", Dead, LVL_IMMORT, false, 0, handler!(|g, id, args, sub| g.do_multiok(id, args))),
+    cmd!("open", "o", Sitting, 0, true, SCMD_OPEN, handler!(|g, id, args, sub| {
+        g.run_acts(id, move |ch, world| Commands::do_gen_door(ch.clone(), world, args, sub))
+    })),
+    cmd!("pick", "pi", Standing, 0, true, SCMD_PICK, handler!(|g, id, args, sub| {
+        g.run_acts(id, move |ch, world| Commands::do_gen_door(ch.clone(), world, args, sub))
+    })),
+    cmd!("put", "p", Resting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_acts(id, |ch, world| Commands::do_put(ch.clone(), world, args))
+    })),
+    cmd!("quit", "quit", Dead, 0, false, 0, handler!(|g, id, args, sub| g.do_quit(id))),
+    cmd!("remove", "rem", Resting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_remove(&mut ch.write(), world, args))
+    })),
+    cmd!("say", "sa", Resting, 0, true, 0, handler!(|g, id, args, sub| g.do_say(id, args.to_string()))),
+    cmd!("score", "sc", Dead, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_score(&ch.read(), world, args))
+    })),
+    cmd!("shout", "sh", Resting, 0, true, 0, handler!(|g, id, args, sub| g.do_shout(id, args.to_string()))),
+    cmd!("tell", "t", Dead, 0, true, 0, handler!(|g, id, args, sub| g.do_tell(id, args.to_string()))),
+    cmd!("take", "ta", Resting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_acts(id, |ch, world| Commands::do_get(ch.clone(), world, args))
+    })),
+    cmd!("unban", "unban", Dead, LVL_IMMORT, false, 0, handler!(|g, id, args, sub| g.do_unban(id, args))),
+    cmd!("unlock", "unl", Sitting, 0, true, SCMD_UNLOCK, handler!(|g, id, args, sub| {
+        g.run_acts(id, move |ch, world| Commands::do_gen_door(ch.clone(), world, args, sub))
+    })),
+    cmd!("wear", "wea", Resting, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_wear(&mut ch.write(), world, args))
+    })),
+    cmd!("who", "wh", Dead, 0, true, 0, handler!(|g, id, args, sub| {
+        g.run_messages(id, |ch, world| Commands::do_who(&ch.read(), world, args))
+    })),
+];
+
+/// Split a line into the command word and its arguments. A leading
+/// non-letter is a command on its own, so "'hello" says hello.
+pub fn split_command(line: &str) -> (String, &str) {
+    let line = line.trim_start();
+    match line.chars().next() {
+        Some(c) if !c.is_alphabetic() => (c.to_string(), line[c.len_utf8()..].trim()),
+        Some(_) => match line.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word.to_lowercase(), rest.trim()),
+            None => (line.to_lowercase(), ""),
+        },
+        None => (String::new(), ""),
+    }
+}
+
+/// Whether `arg` abbreviates any of the words in `namelist`, e.g. "gob"
+/// for "goblin guard" (isname in /web/deltamud/src/handler.c).
+pub fn isname(arg: &str, namelist: &str) -> bool {
+    !arg.is_empty() && namelist.split_whitespace().any(|name| {
+        name.get(..arg.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(arg))
+    })
+}
+
+/// Words skipped when reading arguments, so "get sword from bag" means
+/// "get sword bag" (fill).
+const FILL_WORDS: [&str; 7] = ["in", "from", "with", "the", "on", "at", "to"];
+
+/// The first two arguments, skipping fill words (two_arguments).
+pub fn two_arguments(args: &str) -> (&str, &str) {
+    let mut words = args.split_whitespace()
+        .filter(|w| !FILL_WORDS.iter().any(|fill| fill.eq_ignore_ascii_case(w)));
+    (words.next().unwrap_or(""), words.next().unwrap_or(""))
+}
+
+/// How an object argument picks objects (FIND_x in handler.h).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindDots {
+    /// One object, by name
+    Indiv,
+    /// "all": everything
+    All,
+    /// "all.x": everything called x
+    AllDot,
+}
+
+/// Read "all" or "all.x" off an argument, returning the name left over
+/// (find_all_dots).
+pub fn find_all_dots(arg: &str) -> (FindDots, &str) {
+    if arg.eq_ignore_ascii_case("all") {
+        (FindDots::All, "")
+    } else if arg.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("all.")) {
+        (FindDots::AllDot, &arg[4..])
+    } else {
+        (FindDots::Indiv, arg)
+    }
+}
+
+/// Whether `arg` is a non-empty abbreviation of `word` (is_abbrev).
+pub fn is_abbrev(arg: &str, word: &str) -> bool {
+    !arg.is_empty() && word.get(..arg.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(arg))
+}
+
+/// Find the command `word` selects for a character of `level`.
+pub fn find_command(word: &str, level: Level) -> Option<&'static CommandInfo> {
+    CMD_INFO.iter().find(|cmd| {
+        cmd.name.starts_with(word) && word.len() >= cmd.abbrev.len() && level >= cmd.min_level
+    })
+}
+
+/// Why a character in `position` can't act, for commands that need more.
+pub fn position_message(position: Position) -> &'static str {
+    match position {
+        Position::Dead => "Lie still; you are DEAD!!! :-(",
+        Position::MortalllyWounded | Position::Incapacitated => {
+            "You are in a pretty bad shape, unable to do anything!"
+        }
+        Position::Stunned => "All you can do right now is think about the stars!",
+        Position::Sleeping | Position::Meditating => "In your dreams, or what?",
+        Position::Resting => "Nah... You feel too relaxed to do that..",
+        Position::Sitting => "Maybe you should get on your feet first?",
+        Position::Fighting => "No way!  You're fighting for your life!",
+        Position::Standing => "",
+    }
+}
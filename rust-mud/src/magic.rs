@@ -1,6 +1,7 @@
 use crate::character::{Character, Affect};
 use crate::types::*;
-use crate::combat::{Combat, DamageType};
+use crate::act::{Act, TO_CHAR, TO_ROOM};
+use crate::combat::Combat;
 use std::sync::Arc;
 use parking_lot::RwLock;
 use std::collections::HashMap;
@@ -50,6 +51,32 @@ pub const SKILL_STEAL: i32 = 139;
 pub const SKILL_TRACK: i32 = 140;
 pub const SKILL_DISARM: i32 = 141;
 
+/// The skills do_start in /web/deltamud/src/class.c gives a new
+/// character of each class, and how well.
+const START_SKILLS: &[(Class, i32, i32)] = &[
+    (Class::Thief, SKILL_SNEAK, 10),
+    (Class::Thief, SKILL_HIDE, 5),
+    (Class::Thief, SKILL_STEAL, 15),
+    (Class::Thief, SKILL_BACKSTAB, 10),
+    (Class::Thief, SKILL_PICK_LOCK, 10),
+    (Class::Thief, SKILL_TRACK, 10),
+];
+
+/// How well `ch` knows a skill, as a percentage (GET_SKILL). There's no
+/// practicing yet, so players have what their class starts with and
+/// immortals know everything. Mobs know nothing, as in the C code.
+pub fn get_skill(ch: &Character, skill: i32) -> i32 {
+    if ch.is_npc {
+        0
+    } else if ch.is_immortal() {
+        100
+    } else {
+        START_SKILLS.iter()
+            .find(|(class, s, _)| *class == ch.player.class && *s == skill)
+            .map_or(0, |(_, _, learned)| *learned)
+    }
+}
+
 // Affect locations
 pub const APPLY_NONE: i32 = 0;
 pub const APPLY_STR: i32 = 1;
@@ -74,6 +101,7 @@ pub const AFF_INVISIBLE: i64 = 1 << 1;
 pub const AFF_DETECT_EVIL: i64 = 1 << 2;
 pub const AFF_DETECT_INVIS: i64 = 1 << 3;
 pub const AFF_DETECT_MAGIC: i64 = 1 << 4;
+pub const AFF_SENSE_LIFE: i64 = 1 << 5;
 pub const AFF_SANCTUARY: i64 = 1 << 7;
 pub const AFF_POISON: i64 = 1 << 10;
 pub const AFF_SLEEP: i64 = 1 << 12;
@@ -108,7 +136,8 @@ bitflags::bitflags! {
     }
 }
 
-pub type SpellFunction = fn(level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String;
+/// A spell's effect. Returns the act() messages describing it.
+pub type SpellFunction = fn(level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act>;
 
 lazy_static! {
     pub static ref SPELL_INFO: HashMap<i32, SpellInfo> = {
@@ -198,14 +227,26 @@ lazy_static! {
     };
 }
 
+/// What the caster sees when a spell has no effect.
+const NOEFFECT: &str = "Nothing seems to happen.";
+
+/// The affect spells' messages: one to the victim and one, from the
+/// victim, to those in the room who can see them (mag_affects).
+fn affect_messages(victim: &Arc<RwLock<Character>>, to_vict: &str, to_room: &str) -> Vec<Act> {
+    vec![
+        Act::new(to_vict, victim, TO_CHAR),
+        Act::new(to_room, victim, TO_ROOM).hide_invisible(),
+    ]
+}
+
 // Spell implementations
-fn spell_armor(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_armor(_level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut vic = victim.write();
         
         // Check if already affected
         if vic.affect_flags & AFF_SANCTUARY != 0 {
-            return "Nothing seems to happen.".to_string();
+            return vec![Act::new(NOEFFECT, &ch, TO_CHAR)];
         }
         
         let affect = Affect {
@@ -219,13 +260,14 @@ fn spell_armor(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<Rw
         vic.affected.push(affect);
         vic.points.armor -= 20;
         
-        "$N is surrounded by a magical armor.".to_string()
+        drop(vic);
+        affect_messages(&victim, "You feel someone protecting you.", "$n is surrounded by a magical armor.")
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_bless(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_bless(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut vic = victim.write();
         
@@ -240,13 +282,14 @@ fn spell_bless(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<Rw
         vic.affected.push(affect);
         vic.points.hitroll += 2;
         
-        "$N glows with divine blessing.".to_string()
+        drop(vic);
+        affect_messages(&victim, "You feel righteous.", "$n glows with divine blessing.")
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_cure_light(level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_cure_light(level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut rng = rand::thread_rng();
         let heal = rng.gen_range(1..=8) + (level as i32 / 4);
@@ -254,26 +297,28 @@ fn spell_cure_light(level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Ar
         let mut vic = victim.write();
         vic.points.hit = (vic.points.hit + heal).min(vic.points.max_hit);
         
-        "You feel better!".to_string()
+        drop(vic);
+        vec![Act::new("You feel better.", &victim, TO_CHAR)]
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_heal(level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_heal(level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let heal = 100 + level as i32 * 3;
         
         let mut vic = victim.write();
         vic.points.hit = (vic.points.hit + heal).min(vic.points.max_hit);
         
-        "A warm feeling floods your body!".to_string()
+        drop(vic);
+        vec![Act::new("A warm feeling floods your body.", &victim, TO_CHAR)]
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_magic_missile(level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_magic_missile(level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut rng = rand::thread_rng();
         let missiles = 1 + (level as i32 - 1) / 5;
@@ -283,29 +328,29 @@ fn spell_magic_missile(level: Level, ch: Arc<RwLock<Character>>, victim: Option<
             damage += rng.gen_range(1..=6) + 1;
         }
         
-        Combat::do_damage(ch, victim, damage, DamageType::Fire)
+        Combat::skill_damage(ch, victim, damage, SPELL_MAGIC_MISSILE)
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_fireball(level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_fireball(level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut rng = rand::thread_rng();
         let damage = rng.gen_range(1..=6) * level as i32;
         
-        Combat::do_damage(ch, victim, damage, DamageType::Fire)
+        Combat::skill_damage(ch, victim, damage, SPELL_FIREBALL)
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_invisibility(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_invisibility(_level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut vic = victim.write();
         
         if vic.affect_flags & AFF_INVISIBLE != 0 {
-            return "Nothing seems to happen.".to_string();
+            return vec![Act::new(NOEFFECT, &ch, TO_CHAR)];
         }
         
         let affect = Affect {
@@ -320,18 +365,19 @@ fn spell_invisibility(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option
         vic.affect_flags |= AFF_INVISIBLE;
         vic.points.armor -= 40;
         
-        "$N slowly fades out of existence.".to_string()
+        drop(vic);
+        affect_messages(&victim, "You vanish.", "$n slowly fades out of existence.")
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
-fn spell_sanctuary(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> String {
+fn spell_sanctuary(_level: Level, ch: Arc<RwLock<Character>>, victim: Option<Arc<RwLock<Character>>>) -> Vec<Act> {
     if let Some(victim) = victim {
         let mut vic = victim.write();
         
         if vic.affect_flags & AFF_SANCTUARY != 0 {
-            return "Nothing seems to happen.".to_string();
+            return vec![Act::new(NOEFFECT, &ch, TO_CHAR)];
         }
         
         let affect = Affect {
@@ -345,9 +391,10 @@ fn spell_sanctuary(_level: Level, _ch: Arc<RwLock<Character>>, victim: Option<Ar
         vic.affected.push(affect);
         vic.affect_flags |= AFF_SANCTUARY;
         
-        "$N is surrounded by a white aura.".to_string()
+        drop(vic);
+        affect_messages(&victim, "A white aura momentarily surrounds you.", "$n is surrounded by a white aura.")
     } else {
-        "".to_string()
+        Vec::new()
     }
 }
 
@@ -12,12 +12,32 @@ mod combat;
 mod magic;
 mod file_loader;
 mod commands;
+mod interpreter;
+mod alias;
+mod history;
+mod act;
+mod social;
+mod help;
+mod hedit;
 mod config;
+mod telnet;
+mod gmcp;
+mod msdp;
+mod websocket;
+mod tls;
+mod copyover;
+mod pager;
+mod ban;
+mod throttle;
+mod proxy;
 
-use tokio::net::TcpListener;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use anyhow::Result;
 use log::info;
 use config::Config;
@@ -31,6 +51,8 @@ pub trait DatabaseInterface: Send + Sync {
     async fn load_player(&self, name: &str) -> Result<character::Character>;
     async fn save_player(&self, character: &character::Character) -> Result<()>;
     async fn verify_password(&self, name: &str, password: &str) -> Result<bool>;
+    async fn load_aliases(&self, player_id: u64) -> Result<Vec<alias::Alias>>;
+    async fn save_aliases(&self, player_id: u64, aliases: &[alias::Alias]) -> Result<()>;
 }
 
 // Implement trait for standard database
@@ -46,6 +68,10 @@ impl DatabaseInterface for database::Database {
     async fn verify_password(&self, name: &str, password: &str) -> Result<bool> { 
         self.verify_password(name, password).await 
     }
+    async fn load_aliases(&self, player_id: u64) -> Result<Vec<alias::Alias>> { self.load_aliases(player_id).await }
+    async fn save_aliases(&self, player_id: u64, aliases: &[alias::Alias]) -> Result<()> {
+        self.save_aliases(player_id, aliases).await
+    }
 }
 
 // Implement trait for compat database
@@ -76,6 +102,12 @@ impl DatabaseInterface for database_compat::CompatDatabase {
     async fn verify_password(&self, name: &str, password: &str) -> Result<bool> { 
         self.verify_password_compat(name, password).await 
     }
+    async fn load_aliases(&self, player_id: u64) -> Result<Vec<alias::Alias>> {
+        self.load_aliases_compat(player_id).await
+    }
+    async fn save_aliases(&self, player_id: u64, aliases: &[alias::Alias]) -> Result<()> {
+        self.save_aliases_compat(player_id, aliases).await
+    }
 }
 
 #[tokio::main]
@@ -116,42 +148,213 @@ async fn main() -> Result<()> {
     
     let world = Arc::new(RwLock::new(world));
     
+    // After a copyover, pick up the state the old process left behind
+    let recovered = if std::env::args().any(|a| a == copyover::COPYOVER_FLAG) {
+        info!("Copyover recovery initiated");
+        Some(copyover::load(&config.lib_path)?)
+    } else {
+        None
+    };
+    
+    // Start TCP listener, or take over the old process's one
+    let addr = format!("0.0.0.0:{}", config.port);
+    let listener = match &recovered {
+        Some((state, _)) => {
+            let fd = copyover::reclaim_fd(state.listener_fd)?;
+            let std_listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+            std_listener.set_nonblocking(true)?;
+            TcpListener::from_std(std_listener)?
+        }
+        None => TcpListener::bind(&addr).await?,
+    };
+    info!("Server listening on {}", addr);
+    
+    // Site bans, checked here at accept time and changed in game by ban/unban
+    let bans = Arc::new(RwLock::new(ban::BanList::load(&config.lib_path)));
+    let rate = Arc::new(Mutex::new(throttle::ConnectRate::new(config.max_conns_per_minute)));
+    
+    let proxy_protocol = config.proxy_protocol;
+    
     // Create game message channel
     let (game_tx, game_rx) = mpsc::channel(100);
     
     // Start game loop
     let game_world = world.clone();
     let game_db = db.clone();
-    let lib_path = config.lib_path.clone();
+    let game_config = config.clone();
+    let game_bans = bans.clone();
+    let listener_fd = listener.as_raw_fd();
+    let zone_ages = recovered.as_ref().map(|(state, _)| state.zone_ages.clone());
     let _game_handle = tokio::spawn(async move {
-        let mut game = game::Game::new(game_world, game_db);
-        game.load_text_files(&lib_path).await;
+        let mut game = game::Game::new(game_world, game_db, game_bans, &game_config);
+        game.set_listener_fd(listener_fd);
+        game.load_text_files(&game_config.lib_path).await;
         game.prime_zones();
+        if let Some(ages) = zone_ages {
+            game.restore_zone_ages(&ages);
+        }
         if let Err(e) = game.run(game_rx).await {
             eprintln!("Game loop error: {}", e);
         }
     });
     
-    // Start TCP listener
-    let addr = format!("0.0.0.0:{}", config.port);
-    let listener = TcpListener::bind(&addr).await?;
-    info!("Server listening on {}", addr);
+    // Reattach the sockets inherited across copyover
+    if let Some((state, elapsed)) = recovered {
+        for saved in state.connections {
+            if let Err(e) = copyover::reclaim_fd(saved.fd) {
+                eprintln!("Copyover: lost socket for {}: {}", saved.name, e);
+                continue;
+            }
+            let std_stream = unsafe { std::net::TcpStream::from_raw_fd(saved.fd) };
+            let stream = match std_stream.set_nonblocking(true)
+                .and_then(|_| tokio::net::TcpStream::from_std(std_stream))
+            {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Copyover: lost socket for {}: {}", saved.name, e);
+                    continue;
+                }
+            };
+            let game_tx = game_tx.clone();
+            let id = connection::next_conn_id();
+            tokio::spawn(async move {
+                let addr = saved.addr;
+                if let Err(e) = connection::resume_client(stream, id, saved, elapsed, game_tx).await {
+                    eprintln!("Error handling client {}: {}", addr, e);
+                }
+            });
+        }
+    }
     
-    let mut conn_id = 1u64;
+    // Start WebSocket listener for the web client
+    if let Some(ws_port) = config.websocket_port {
+        let ws_addr = format!("0.0.0.0:{}", ws_port);
+        let ws_listener = TcpListener::bind(&ws_addr).await?;
+        info!("WebSocket listening on {}", ws_addr);
+        let game_tx = game_tx.clone();
+        let bans = bans.clone();
+        let rate = rate.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, peer) = match ws_listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        eprintln!("WebSocket accept error: {}", e);
+                        continue;
+                    }
+                };
+                let game_tx = game_tx.clone();
+                let bans = bans.clone();
+                let rate = rate.clone();
+                let id = connection::next_conn_id();
+                tokio::spawn(async move {
+                    let addr = match client_addr(&mut stream, peer, proxy_protocol).await {
+                        Some(addr) => addr,
+                        None => return,
+                    };
+                    if ban::refused(&bans, addr) || !throttle::admit(&rate, addr) {
+                        return;
+                    }
+                    if let Err(e) = websocket::handle_websocket(stream, addr, id, game_tx).await {
+                        eprintln!("Error handling WebSocket client {}: {}", addr, e);
+                    }
+                });
+            }
+        });
+    }
+    
+    // Start TLS listener for encrypted telnet
+    if let Some(tls_port) = config.tls_port {
+        let (cert_path, key_path) = match (&config.tls_cert_path, &config.tls_key_path) {
+            (Some(cert), Some(key)) => (cert, key),
+            _ => return Err(anyhow::anyhow!("MUD_TLS_PORT requires MUD_TLS_CERT and MUD_TLS_KEY")),
+        };
+        let acceptor = tls::load_acceptor(cert_path, key_path)?;
+        let tls_addr = format!("0.0.0.0:{}", tls_port);
+        let tls_listener = TcpListener::bind(&tls_addr).await?;
+        info!("TLS listening on {}", tls_addr);
+        let game_tx = game_tx.clone();
+        let bans = bans.clone();
+        let rate = rate.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, peer) = match tls_listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        eprintln!("TLS accept error: {}", e);
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let game_tx = game_tx.clone();
+                let bans = bans.clone();
+                let rate = rate.clone();
+                let id = connection::next_conn_id();
+                tokio::spawn(async move {
+                    let addr = match client_addr(&mut stream, peer, proxy_protocol).await {
+                        Some(addr) => addr,
+                        None => return,
+                    };
+                    if ban::refused(&bans, addr) || !throttle::admit(&rate, addr) {
+                        return;
+                    }
+                    let stream = match tls::accept(&acceptor, stream).await {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            eprintln!("TLS handshake with {} failed: {}", addr, e);
+                            return;
+                        }
+                    };
+                    if let Err(e) = connection::handle_client(stream, addr, id, connection::Transport::Tls, None, game_tx).await {
+                        eprintln!("Error handling TLS client {}: {}", addr, e);
+                    }
+                });
+            }
+        });
+    }
     
     loop {
-        let (stream, addr) = listener.accept().await?;
+        let (mut stream, peer) = listener.accept().await?;
         let game_tx = game_tx.clone();
-        let id = conn_id;
-        conn_id += 1;
+        let bans = bans.clone();
+        let rate = rate.clone();
+        let id = connection::next_conn_id();
+        let fd = stream.as_raw_fd();
         
         tokio::spawn(async move {
-            if let Err(e) = connection::handle_client(stream, addr, id, game_tx).await {
+            let addr = match client_addr(&mut stream, peer, proxy_protocol).await {
+                Some(addr) => addr,
+                None => return,
+            };
+            if ban::refused(&bans, addr) {
+                let _ = stream.write_all(b"Your site is BANNED!\r\n").await;
+                return;
+            }
+            if !throttle::admit(&rate, addr) {
+                return;
+            }
+            if let Err(e) = connection::handle_client(stream, addr, id, connection::Transport::Telnet, Some(fd), game_tx).await {
                 eprintln!("Error handling client {}: {}", addr, e);
             }
         });
     }
 }
 
+/// Where a new connection is really from: the socket's peer, or with
+/// MUD_PROXY_PROTOCOL the client the load balancer names in its PROXY
+/// header. None drops the connection.
+async fn client_addr(stream: &mut TcpStream, peer: SocketAddr, proxy_protocol: bool) -> Option<SocketAddr> {
+    if !proxy_protocol {
+        return Some(peer);
+    }
+    match proxy::read_header(stream, peer).await {
+        Ok(addr) => Some(addr),
+        Err(e) => {
+            eprintln!("Bad PROXY header from {}: {}", peer, e);
+            None
+        }
+    }
+}
+
 // Add bitflags dependency
 extern crate bitflags;
\ No newline at end of file
@@ -1,4 +1,5 @@
 // Mock database for testing without MySQL
+use crate::alias::Alias;
 use crate::character::Character;
 use anyhow::Result;
 use std::collections::HashMap;
@@ -6,12 +7,14 @@ use std::sync::Mutex;
 
 pub struct MockDatabase {
     players: Mutex<HashMap<String, Character>>,
+    aliases: Mutex<HashMap<u64, Vec<Alias>>>,
 }
 
 impl MockDatabase {
     pub fn new() -> Self {
         MockDatabase {
             players: Mutex::new(HashMap::new()),
+            aliases: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -58,4 +61,14 @@ impl crate::DatabaseInterface for MockDatabase {
         let players = self.players.lock().unwrap();
         Ok(players.contains_key(name) || password == "test")
     }
+    
+    async fn load_aliases(&self, player_id: u64) -> Result<Vec<Alias>> {
+        let aliases = self.aliases.lock().unwrap();
+        Ok(aliases.get(&player_id).cloned().unwrap_or_default())
+    }
+    
+    async fn save_aliases(&self, player_id: u64, aliases: &[Alias]) -> Result<()> {
+        self.aliases.lock().unwrap().insert(player_id, aliases.to_vec());
+        Ok(())
+    }
 }
\ No newline at end of file
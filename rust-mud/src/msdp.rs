@@ -0,0 +1,201 @@
+// MSDP (MUD Server Data Protocol, telnet option 69)
+//
+// The older sibling of GMCP for clients that don't speak JSON. Data goes
+// out as IAC SB MSDP MSDP_VAR <name> MSDP_VAL <value> ... IAC SE, and the
+// client drives it with LIST/REPORT/UNREPORT/SEND/RESET commands encoded
+// the same way. Variables are computed from the same character and room
+// state the prompt and `look` use, so both views always agree.
+
+use crate::character::Character;
+use crate::telnet::{self, TELOPT_MSDP};
+use crate::types::NUM_OF_DIRS;
+
+pub const MSDP_VAR: u8 = 1;
+pub const MSDP_VAL: u8 = 2;
+pub const MSDP_TABLE_OPEN: u8 = 3;
+pub const MSDP_TABLE_CLOSE: u8 = 4;
+pub const MSDP_ARRAY_OPEN: u8 = 5;
+pub const MSDP_ARRAY_CLOSE: u8 = 6;
+
+/// Commands the client may send, answered by LIST COMMANDS.
+pub const COMMANDS: &[&str] = &["LIST", "REPORT", "UNREPORT", "RESET", "SEND"];
+
+/// Lists the client may ask for with LIST, answered by LIST LISTS.
+pub const LISTS: &[&str] = &[
+    "COMMANDS", "LISTS", "REPORTABLE_VARIABLES", "REPORTED_VARIABLES", "SENDABLE_VARIABLES",
+];
+
+/// Every variable we can send or report.
+pub const REPORTABLE_VARIABLES: &[&str] = &[
+    "SERVER_ID", "CHARACTER_NAME", "LEVEL", "CLASS", "RACE", "POSITION",
+    "HEALTH", "HEALTH_MAX", "MANA", "MANA_MAX", "MOVEMENT", "MOVEMENT_MAX",
+    "GOLD", "EXPERIENCE", "OPPONENT_NAME",
+    "ROOM_VNUM", "ROOM_NAME", "ROOM_TERRAIN", "ROOM_EXITS", "AREA_NAME",
+];
+
+const DIR_NAMES: [&str; NUM_OF_DIRS] = ["n", "e", "s", "w", "u", "d"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MsdpValue {
+    Str(String),
+    Array(Vec<MsdpValue>),
+    Table(Vec<(String, MsdpValue)>),
+}
+
+impl MsdpValue {
+    fn from_list(items: &[&str]) -> Self {
+        MsdpValue::Array(items.iter().map(|s| MsdpValue::Str(s.to_string())).collect())
+    }
+}
+
+/// Current value of every reportable variable. `zone_name` comes from the
+/// world's zone table, which the character doesn't carry itself.
+pub fn variables(ch: Option<&Character>, zone_name: Option<&str>) -> Vec<(&'static str, MsdpValue)> {
+    let str_val = MsdpValue::Str;
+    let mut vars = vec![("SERVER_ID", str_val("DeltaMUD".to_string()))];
+
+    let ch = match ch {
+        Some(ch) => ch,
+        None => return vars,
+    };
+
+    let opponent = ch.fighting.as_ref()
+        .and_then(|w| w.upgrade())
+        .map(|v| v.read().display_for_others())
+        .unwrap_or_default();
+
+    vars.extend([
+        ("CHARACTER_NAME", str_val(ch.get_name().to_string())),
+        ("LEVEL", str_val(ch.player.level.to_string())),
+        ("CLASS", str_val(format!("{:?}", ch.player.class))),
+        ("RACE", str_val(format!("{:?}", ch.player.race))),
+        ("POSITION", str_val(format!("{:?}", ch.position))),
+        ("HEALTH", str_val(ch.points.hit.to_string())),
+        ("HEALTH_MAX", str_val(ch.points.max_hit.to_string())),
+        ("MANA", str_val(ch.points.mana.to_string())),
+        ("MANA_MAX", str_val(ch.points.max_mana.to_string())),
+        ("MOVEMENT", str_val(ch.points.move_points.to_string())),
+        ("MOVEMENT_MAX", str_val(ch.points.max_move.to_string())),
+        ("GOLD", str_val(ch.points.gold.to_string())),
+        ("EXPERIENCE", str_val(ch.points.exp.to_string())),
+        ("OPPONENT_NAME", str_val(opponent)),
+    ]);
+
+    if let Some(room) = ch.in_room.as_ref().and_then(|w| w.upgrade()) {
+        let room = room.read();
+        let exits = room.exits.iter().enumerate()
+            .filter_map(|(dir, exit)| {
+                exit.as_ref().map(|e| (DIR_NAMES[dir].to_string(), str_val(e.to_room.to_string())))
+            })
+            .collect();
+        vars.extend([
+            ("ROOM_VNUM", str_val(room.number.to_string())),
+            ("ROOM_NAME", str_val(room.name.clone())),
+            ("ROOM_TERRAIN", str_val(format!("{:?}", room.sector_type))),
+            ("ROOM_EXITS", MsdpValue::Table(exits)),
+            ("AREA_NAME", str_val(zone_name.unwrap_or("").to_string())),
+        ]);
+    }
+
+    vars
+}
+
+/// Answer a LIST request, or None for a list we don't know.
+pub fn list(name: &str, reported: &[String]) -> Option<MsdpValue> {
+    match name {
+        "COMMANDS" => Some(MsdpValue::from_list(COMMANDS)),
+        "LISTS" => Some(MsdpValue::from_list(LISTS)),
+        "REPORTABLE_VARIABLES" | "SENDABLE_VARIABLES" => {
+            Some(MsdpValue::from_list(REPORTABLE_VARIABLES))
+        }
+        "REPORTED_VARIABLES" => {
+            Some(MsdpValue::Array(reported.iter().cloned().map(MsdpValue::Str).collect()))
+        }
+        _ => None,
+    }
+}
+
+/// Frame variables as a complete telnet subnegotiation.
+pub fn encode(vars: &[(&str, MsdpValue)]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for (name, value) in vars {
+        payload.push(MSDP_VAR);
+        payload.extend_from_slice(name.as_bytes());
+        encode_value(value, &mut payload);
+    }
+    telnet::subnegotiation(TELOPT_MSDP, &payload)
+}
+
+fn encode_value(value: &MsdpValue, out: &mut Vec<u8>) {
+    out.push(MSDP_VAL);
+    match value {
+        MsdpValue::Str(s) => out.extend_from_slice(s.as_bytes()),
+        MsdpValue::Array(items) => {
+            out.push(MSDP_ARRAY_OPEN);
+            for item in items {
+                encode_value(item, out);
+            }
+            out.push(MSDP_ARRAY_CLOSE);
+        }
+        MsdpValue::Table(entries) => {
+            out.push(MSDP_TABLE_OPEN);
+            for (key, item) in entries {
+                out.push(MSDP_VAR);
+                out.extend_from_slice(key.as_bytes());
+                encode_value(item, out);
+            }
+            out.push(MSDP_TABLE_CLOSE);
+        }
+    }
+}
+
+/// Split a client payload into (command, arguments). Arrays are
+/// flattened, so "REPORT" with an array of names and "REPORT" with
+/// several plain values come out the same.
+pub fn decode(payload: &[u8]) -> Vec<(String, Vec<String>)> {
+    let mut commands: Vec<(String, Vec<String>)> = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    let mut in_var = false;
+
+    for &byte in payload {
+        match byte {
+            MSDP_VAR => {
+                finish(&mut current, in_var, &mut commands);
+                in_var = true;
+                current = Some(Vec::new());
+            }
+            MSDP_VAL => {
+                finish(&mut current, in_var, &mut commands);
+                in_var = false;
+                current = Some(Vec::new());
+            }
+            MSDP_TABLE_OPEN | MSDP_TABLE_CLOSE | MSDP_ARRAY_OPEN | MSDP_ARRAY_CLOSE => {
+                finish(&mut current, in_var, &mut commands);
+                in_var = false;
+            }
+            _ => {
+                if let Some(buf) = current.as_mut() {
+                    buf.push(byte);
+                }
+            }
+        }
+    }
+    finish(&mut current, in_var, &mut commands);
+
+    commands
+}
+
+/// Close off the name or value being collected and attach it to the
+/// command list.
+fn finish(buf: &mut Option<Vec<u8>>, in_var: bool, commands: &mut Vec<(String, Vec<String>)>) {
+    if let Some(bytes) = buf.take() {
+        let text = String::from_utf8_lossy(&bytes).trim().to_uppercase();
+        if in_var {
+            commands.push((text, Vec::new()));
+        } else if !text.is_empty() {
+            if let Some((_, args)) = commands.last_mut() {
+                args.push(text);
+            }
+        }
+    }
+}
@@ -4,7 +4,8 @@ use crate::room::Room;
 use std::sync::{Arc, Weak};
 use parking_lot::RwLock;
 
-// Object types
+// Object types, numbered as in the object files (ITEM_x in
+// /web/deltamud/src/structs.h)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ObjectType {
@@ -13,18 +14,44 @@ pub enum ObjectType {
     Wand = 3,
     Staff = 4,
     Weapon = 5,
-    Treasure = 6,
-    Armor = 7,
-    Potion = 8,
-    Other = 9,
-    Trash = 10,
-    Container = 11,
-    Note = 12,
-    LiqContainer = 13,
-    Key = 14,
-    Food = 15,
-    Money = 16,
-    Fountain = 17,
+    Treasure = 8,
+    Armor = 9,
+    Potion = 10,
+    Other = 12,
+    Trash = 13,
+    Container = 15,
+    Note = 16,
+    LiqContainer = 17,
+    Key = 18,
+    Food = 19,
+    Money = 20,
+    Fountain = 23,
+}
+
+impl ObjectType {
+    /// The type stored as `n` in an object file. Types nothing here
+    /// handles yet (boats, portals, ...) load as Other.
+    pub fn from_i32(n: i32) -> ObjectType {
+        match n {
+            1 => ObjectType::Light,
+            2 => ObjectType::Scroll,
+            3 => ObjectType::Wand,
+            4 => ObjectType::Staff,
+            5 => ObjectType::Weapon,
+            8 => ObjectType::Treasure,
+            9 => ObjectType::Armor,
+            10 => ObjectType::Potion,
+            13 => ObjectType::Trash,
+            15 => ObjectType::Container,
+            16 => ObjectType::Note,
+            17 => ObjectType::LiqContainer,
+            18 => ObjectType::Key,
+            19 => ObjectType::Food,
+            20 => ObjectType::Money,
+            23 => ObjectType::Fountain,
+            _ => ObjectType::Other,
+        }
+    }
 }
 
 // Object wear flags
@@ -88,6 +115,14 @@ pub struct ObjectValues {
     pub value: [i32; 4],
 }
 
+// Container flags, kept in value[1] of a container (CONT_x in
+// /web/deltamud/src/structs.h). value[0] is the weight it holds and
+// value[2] the vnum of its key.
+pub const CONT_CLOSEABLE: i32 = 1 << 0;
+pub const CONT_PICKPROOF: i32 = 1 << 1;
+pub const CONT_CLOSED: i32 = 1 << 2;
+pub const CONT_LOCKED: i32 = 1 << 3;
+
 // Main object structure
 #[derive(Debug)]
 pub struct Object {
@@ -159,6 +194,21 @@ impl Object {
         self.obj_type == ObjectType::Container
     }
     
+    /// Whether this is a container with `flag` set in its CONT_x flags.
+    pub fn cont_flagged(&self, flag: i32) -> bool {
+        self.is_container() && self.values.value[1] & flag != 0
+    }
+
+    /// How much weight a container holds.
+    pub fn capacity(&self) -> i32 {
+        self.values.value[0]
+    }
+
+    /// The first of its keywords (fname).
+    pub fn fname(&self) -> &str {
+        self.name.split_whitespace().next().unwrap_or("")
+    }
+
     pub fn can_wear(&self, position: WearFlags) -> bool {
         self.wear_flags.contains(position)
     }
@@ -0,0 +1,110 @@
+// Output paging
+//
+// Port of page_string/show_string (/web/deltamud/src/modify.c). Long
+// output is cut into pages of the player's page_length and shown one at a
+// time, with a prompt in between that takes RETURN, (q)uit, (r)efresh,
+// (b)ack or a page number.
+
+/// Cut text into pages of `page_length` lines, counting a line that runs
+/// past `width` columns as wrapping onto the next. Color codes (&x and
+/// ANSI escapes) take up no columns.
+fn paginate(text: &str, page_length: usize, width: usize) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut start = 0;
+    let mut line = 1;
+    let mut col = 1;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if line > page_length {
+            pages.push(text[start..i].to_string());
+            start = i;
+            line = 1;
+            col = 1;
+        }
+        match c {
+            '\x1b' => {
+                // Skip to the end of the escape sequence
+                for (_, c) in chars.by_ref() {
+                    if c == 'm' {
+                        break;
+                    }
+                }
+            }
+            '&' => {
+                chars.next();
+            }
+            '\r' => col = 1,
+            '\n' => line += 1,
+            _ => {
+                col += 1;
+                if col > width {
+                    col = 1;
+                    line += 1;
+                }
+            }
+        }
+    }
+    if start < text.len() {
+        pages.push(text[start..].to_string());
+    }
+    pages
+}
+
+pub struct Pager {
+    pages: Vec<String>,
+    /// The next page to show
+    page: usize,
+}
+
+impl Pager {
+    pub fn new(text: &str, page_length: usize, width: usize) -> Self {
+        Pager {
+            pages: paginate(text, page_length.max(1), width.max(1)),
+            page: 0,
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Whether the last page has been shown.
+    pub fn finished(&self) -> bool {
+        self.page >= self.pages.len()
+    }
+
+    /// Handle a line typed at the paging prompt (empty for the first
+    /// page). Returns the page to show, None if the player quit, or an
+    /// error message for input that isn't a paging command.
+    pub fn respond(&mut self, input: &str) -> Result<Option<String>, &'static str> {
+        let arg = input.split_whitespace().next().unwrap_or("").to_lowercase();
+        match arg.chars().next() {
+            None => {}
+            Some('q') => {
+                self.page = self.pages.len();
+                return Ok(None);
+            }
+            Some('r') => self.page = self.page.saturating_sub(1),
+            Some('b') => self.page = self.page.saturating_sub(2),
+            Some(c) if c.is_ascii_digit() => {
+                let n: usize = arg.parse().unwrap_or(1);
+                self.page = n.saturating_sub(1).min(self.pages.len() - 1);
+            }
+            Some(_) => {
+                return Err("Valid commands while paging are RETURN, Q, R, B, or a numeric value.");
+            }
+        }
+        let text = self.pages.get(self.page).cloned();
+        self.page += 1;
+        Ok(text)
+    }
+
+    pub fn prompt(&self) -> String {
+        format!(
+            "\r[ Return to continue, (q)uit, (r)efresh, (b)ack, or page number ({}/{}) ]",
+            self.page,
+            self.pages.len()
+        )
+    }
+}
@@ -0,0 +1,101 @@
+// PROXY protocol
+//
+// Behind a TCP load balancer every socket's peer is the balancer. With
+// MUD_PROXY_PROTOCOL set, each connection must open with a HAProxy PROXY
+// header (version 1 text or version 2 binary) naming the real client,
+// and that address is used for bans, rate limits, logs and the saved
+// login host instead. The header is read byte-exact so nothing the client
+// sends after it is lost.
+
+use anyhow::{anyhow, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::time::{timeout, Duration};
+
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Longest possible version 1 header, CRLF included.
+const V1_MAX_LEN: usize = 107;
+
+/// How long the balancer gets to send the header.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read the PROXY header off a fresh connection and return the client
+/// address it gives. Health checks from the balancer itself (v1 UNKNOWN,
+/// v2 LOCAL) keep the socket's own `peer` address.
+pub async fn read_header<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> Result<SocketAddr> {
+    timeout(HEADER_TIMEOUT, read_header_inner(stream, peer))
+        .await
+        .map_err(|_| anyhow!("timed out waiting for PROXY header"))?
+}
+
+async fn read_header_inner<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> Result<SocketAddr> {
+    // Both versions are at least 12 bytes ("PROXY UNKNOWN\r\n" is 15)
+    let mut start = [0u8; 12];
+    stream.read_exact(&mut start).await?;
+
+    if &start == V2_SIGNATURE {
+        read_v2(stream, peer).await
+    } else if start.starts_with(b"PROXY ") {
+        read_v1(stream, &start, peer).await
+    } else {
+        Err(anyhow!("connection did not start with a PROXY header"))
+    }
+}
+
+async fn read_v1<S: AsyncRead + Unpin>(stream: &mut S, start: &[u8], peer: SocketAddr) -> Result<SocketAddr> {
+    let mut line = start.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LEN {
+            return Err(anyhow!("PROXY v1 header too long"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2])?;
+    let fields: Vec<&str> = line.split(' ').collect();
+    match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(peer),
+        ["PROXY", "TCP4" | "TCP6", src, _dst, sport, _dport] => {
+            let ip: IpAddr = src.parse()?;
+            let port: u16 = sport.parse()?;
+            Ok(SocketAddr::new(ip, port))
+        }
+        _ => Err(anyhow!("malformed PROXY v1 header: {}", line)),
+    }
+}
+
+async fn read_v2<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> Result<SocketAddr> {
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    let (ver_cmd, family) = (head[0], head[1]);
+    let len = u16::from_be_bytes([head[2], head[3]]) as usize;
+
+    if ver_cmd >> 4 != 2 {
+        return Err(anyhow!("unsupported PROXY version {}", ver_cmd >> 4));
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+
+    // LOCAL: the balancer talking for itself
+    if ver_cmd & 0x0f == 0 {
+        return Ok(peer);
+    }
+    match family {
+        // TCP over IPv4: src addr, dst addr, src port, dst port
+        0x11 if len >= 12 => {
+            let ip = Ipv4Addr::new(body[0], body[1], body[2], body[3]);
+            let port = u16::from_be_bytes([body[8], body[9]]);
+            Ok(SocketAddr::new(IpAddr::V4(ip), port))
+        }
+        // TCP over IPv6
+        0x21 if len >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&body[..16]);
+            let port = u16::from_be_bytes([body[32], body[33]]);
+            Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port))
+        }
+        // UNSPEC, UDP or unix sockets carry no usable client address
+        _ => Ok(peer),
+    }
+}
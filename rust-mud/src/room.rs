@@ -4,6 +4,13 @@ use crate::object::Object;
 use std::sync::{Arc, Weak};
 use parking_lot::RwLock;
 
+// Exit flags, kept in Exit.exit_info (EX_x in /web/deltamud/src/structs.h)
+pub const EX_ISDOOR: i32 = 1 << 0;
+pub const EX_CLOSED: i32 = 1 << 1;
+pub const EX_LOCKED: i32 = 1 << 2;
+pub const EX_PICKPROOF: i32 = 1 << 3;
+pub const EX_HIDDEN: i32 = 1 << 4;
+
 // Room exit/direction data
 #[derive(Debug, Clone)]
 pub struct Exit {
@@ -14,6 +21,47 @@ pub struct Exit {
     pub to_room: RoomVnum,
 }
 
+impl Exit {
+    /// The EX_x flags for the door field of a room file's exit: 1 is a
+    /// door, 2 a pickproof door, and 3 more on top of either hides the
+    /// exit (setup_dir in /web/deltamud/src/db.c).
+    pub fn flags_from_file(door: i32) -> i32 {
+        let (hidden, door) = if door > 2 { (EX_HIDDEN, door - 3) } else { (0, door) };
+        hidden | match door {
+            1 => EX_ISDOOR,
+            2 => EX_ISDOOR | EX_PICKPROOF,
+            _ => 0,
+        }
+    }
+
+    pub fn is_door(&self) -> bool {
+        self.exit_info & EX_ISDOOR != 0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.exit_info & EX_CLOSED != 0
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.exit_info & EX_LOCKED != 0
+    }
+
+    pub fn is_pickproof(&self) -> bool {
+        self.exit_info & EX_PICKPROOF != 0
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.exit_info & EX_HIDDEN != 0
+    }
+
+    /// What to call the door: the first word of its keywords (fname).
+    pub fn door_name(&self) -> &str {
+        self.keyword.as_deref()
+            .and_then(|k| k.split_whitespace().next())
+            .unwrap_or("door")
+    }
+}
+
 // Sector types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -145,17 +193,6 @@ impl Room {
         }
     }
     
-    pub fn send_to_room(&self, _message: &str) {
-        // Send message to all characters in room
-        for char_ref in &self.people {
-            if let Some(character) = char_ref.upgrade() {
-                // TODO: Send message through character's descriptor
-                let _ch = character.read();
-                // ch.send_message(message);
-            }
-        }
-    }
-    
     pub fn count_people(&self) -> usize {
         self.people.iter()
             .filter(|ch| ch.upgrade().is_some())
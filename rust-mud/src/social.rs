@@ -0,0 +1,225 @@
+// Socials
+//
+// Port of boot_social_messages from /web/deltamud/src/act.social.c. Each
+// social in lib/misc/socials is a header line
+//
+//   ~name sort_as hide min_char_pos min_victim_pos min_level
+//
+// followed by thirteen message lines, '#' marking one the social doesn't
+// have: no argument (to the actor, to the room), victim found (actor,
+// room, victim), victim not found, used on yourself (actor, room), a body
+// part of the victim (actor, room, victim) and an object (actor, room).
+// The file ends with a line holding just '$'.
+
+use crate::act::{act, can_see_obj, ActArgs, TO_CHAR, TO_NOTVICT, TO_ROOM, TO_SLEEP, TO_VICT};
+use crate::character::Character;
+use crate::interpreter::isname;
+use crate::object::Object;
+use crate::types::{Level, Position};
+use log::{info, warn};
+use std::path::Path;
+
+pub const SOCIAL_FILE: &str = "misc/socials";
+
+#[derive(Debug, Clone, Default)]
+pub struct Social {
+    pub command: String,
+    pub sort_as: String,
+    /// Whether the room messages are hidden from those who can't see the
+    /// actor
+    pub hide: bool,
+    pub min_char_position: Position,
+    pub min_victim_position: Position,
+    pub min_level: Level,
+
+    pub char_no_arg: Option<String>,
+    pub others_no_arg: Option<String>,
+    pub char_found: Option<String>,
+    pub others_found: Option<String>,
+    pub vict_found: Option<String>,
+    pub not_found: Option<String>,
+    pub char_auto: Option<String>,
+    pub others_auto: Option<String>,
+    pub char_body_found: Option<String>,
+    pub others_body_found: Option<String>,
+    pub vict_body_found: Option<String>,
+    pub char_obj_found: Option<String>,
+    pub others_obj_found: Option<String>,
+}
+
+#[derive(Default)]
+pub struct SocialTable {
+    socials: Vec<Social>,
+}
+
+impl SocialTable {
+    /// Read lib/misc/socials. A missing or damaged file leaves whatever
+    /// socials could be read, rather than stopping the boot like C did.
+    pub fn load(lib_path: &str) -> Self {
+        let path = Path::new(lib_path).join(SOCIAL_FILE);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("Can't open socials file {}: {}", path.display(), e);
+                return SocialTable::default();
+            }
+        };
+
+        let mut socials = Vec::new();
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('$') {
+                break;
+            }
+            let social = match parse_header(line) {
+                Some(social) => social,
+                None => {
+                    warn!("Format error in {} near '{}'", path.display(), line);
+                    break;
+                }
+            };
+            match read_messages(social, &mut lines) {
+                Some(social) => socials.push(social),
+                None => {
+                    warn!("Unexpected end of {} in social '{}'", path.display(), line);
+                    break;
+                }
+            }
+        }
+
+        // The table is listed and searched in sort_as order
+        socials.sort_by(|a, b| a.sort_as.cmp(&b.sort_as));
+        info!("Social table contains {} socials.", socials.len());
+        SocialTable { socials }
+    }
+
+    /// The social `word` abbreviates that a character of `level` may use.
+    /// Regular commands are checked first, so socials only get words the
+    /// command table doesn't claim.
+    pub fn find(&self, word: &str, level: Level) -> Option<&Social> {
+        self.socials.iter().find(|s| s.command.starts_with(word) && level >= s.min_level)
+    }
+}
+
+fn parse_header(line: &str) -> Option<Social> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 6 {
+        return None;
+    }
+    let command = fields[0].strip_prefix('~')?;
+    let number = |i: usize| fields[i].parse::<i32>().ok();
+    Some(Social {
+        command: command.to_lowercase(),
+        sort_as: fields[1].to_lowercase(),
+        hide: number(2)? != 0,
+        min_char_position: Position::from_i32(number(3)?),
+        min_victim_position: Position::from_i32(number(4)?),
+        min_level: number(5)?.try_into().ok()?,
+        ..Social::default()
+    })
+}
+
+/// Read the thirteen message lines after a header (fread_action).
+fn read_messages<'a>(mut social: Social, lines: &mut impl Iterator<Item = &'a str>) -> Option<Social> {
+    let mut next = || -> Option<Option<String>> {
+        let line = lines.next()?;
+        Some(if line.starts_with('#') { None } else { Some(line.to_string()) })
+    };
+    social.char_no_arg = next()?;
+    social.others_no_arg = next()?;
+    social.char_found = next()?;
+    social.others_found = next()?;
+    social.vict_found = next()?;
+    social.not_found = next()?;
+    social.char_auto = next()?;
+    social.others_auto = next()?;
+    social.char_body_found = next()?;
+    social.others_body_found = next()?;
+    social.vict_body_found = next()?;
+    social.char_obj_found = next()?;
+    social.others_obj_found = next()?;
+    Some(social)
+}
+
+/// Work out what everyone sees when `ch` uses `social` with `args`
+/// (do_action). `people` is everyone else in the room and `objects` what
+/// `ch` carries followed by what lies in the room. Returns the character
+/// each message goes to, the actor included.
+pub fn perform(
+    social: &Social,
+    ch: &Character,
+    people: &[&Character],
+    objects: &[&Object],
+    args: &str,
+) -> Vec<(u64, String)> {
+    let mut words = args.split_whitespace();
+    let target = words.next().unwrap_or("").to_lowercase();
+    let part = words.next();
+    let mut out = Vec::new();
+    let mut act = |msg: &Option<String>, hide: bool, args: &ActArgs, to: u8| {
+        if let Some(msg) = msg {
+            out.extend(act(msg, hide, args, to, people));
+        }
+    };
+
+    if social.char_body_found.is_none() && part.is_some() {
+        return vec![(ch.id, "Sorry, this social does not support body parts.".to_string())];
+    }
+
+    // The actor's own lines are send_to_char in C, which reaches a
+    // sleeper (snore, daydream)
+    if target.is_empty() || social.char_found.is_none() {
+        let args = ActArgs::new(ch);
+        act(&social.char_no_arg, false, &args, TO_CHAR | TO_SLEEP);
+        act(&social.others_no_arg, social.hide, &args, TO_ROOM);
+        return out;
+    }
+
+    if target == "self" || isname(&target, &ch.player.name) {
+        let args = ActArgs::new(ch);
+        match &social.char_auto {
+            Some(_) => act(&social.char_auto, false, &args, TO_CHAR | TO_SLEEP),
+            None => act(&Some("Erm, no.".to_string()), false, &args, TO_CHAR | TO_SLEEP),
+        }
+        act(&social.others_auto, social.hide, &args, TO_ROOM);
+        return out;
+    }
+
+    let vict = match people.iter().copied().find(|p| ch.can_see(p) && isname(&target, &p.player.name)) {
+        Some(vict) => vict,
+        None => {
+            let obj = objects.iter().copied().find(|o| can_see_obj(ch, o) && isname(&target, &o.name));
+            match obj {
+                Some(obj) if social.char_obj_found.is_some() => {
+                    let args = ActArgs::new(ch).obj(obj);
+                    act(&social.char_obj_found, social.hide, &args, TO_CHAR);
+                    act(&social.others_obj_found, social.hide, &args, TO_ROOM);
+                }
+                _ => {
+                    let message = social.not_found.as_deref().unwrap_or("I don't see anything by that name here.");
+                    act(&Some(message.to_string()), false, &ActArgs::new(ch), TO_CHAR | TO_SLEEP);
+                }
+            }
+            return out;
+        }
+    };
+
+    let args = ActArgs::new(ch).vict(vict);
+    if vict.position < social.min_victim_position {
+        act(&Some("$N is not in a proper position for that.".to_string()), false, &args, TO_CHAR | TO_SLEEP);
+    } else if let Some(part) = part {
+        let args = args.text(part);
+        act(&social.char_body_found, false, &args, TO_CHAR | TO_SLEEP);
+        act(&social.others_body_found, social.hide, &args, TO_NOTVICT);
+        act(&social.vict_body_found, social.hide, &args, TO_VICT);
+    } else {
+        act(&social.char_found, false, &args, TO_CHAR | TO_SLEEP);
+        act(&social.others_found, social.hide, &args, TO_NOTVICT);
+        act(&social.vict_found, social.hide, &args, TO_VICT);
+    }
+    out
+}
@@ -0,0 +1,479 @@
+// Telnet protocol handling (RFC 854/855)
+//
+// The C server only ever emitted a couple of hand-built IAC sequences
+// (see /web/deltamud/src/comm.c echo_off/echo_on) and let everything the
+// client sent fall through into the input buffer. Modern MUD clients
+// (Mudlet, TinTin++, MUSHclient) open with a burst of negotiation, so
+// the Rust side strips and interprets it here before a line ever reaches
+// GameMessage::Input.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Telnet commands
+pub const IAC: u8 = 255;
+pub const DONT: u8 = 254;
+pub const DO: u8 = 253;
+pub const WONT: u8 = 252;
+pub const WILL: u8 = 251;
+pub const SB: u8 = 250;
+pub const SE: u8 = 240;
+
+// Telnet options
+pub const TELOPT_ECHO: u8 = 1;
+pub const TELOPT_SGA: u8 = 3;
+pub const TELOPT_TTYPE: u8 = 24;
+pub const TELOPT_NAWS: u8 = 31;
+pub const TELOPT_MSDP: u8 = 69;
+pub const TELOPT_COMPRESS2: u8 = 86;
+pub const TELOPT_GMCP: u8 = 201;
+
+// TERMINAL-TYPE subnegotiation codes (RFC 1091)
+pub const TTYPE_IS: u8 = 0;
+pub const TTYPE_SEND: u8 = 1;
+
+// MUD Terminal Type Standard bits, reported as "MTTS <n>" on the third
+// TERMINAL-TYPE round. See https://tintin.mudhalla.net/protocols/mtts/
+pub const MTTS_ANSI: u32 = 1;
+pub const MTTS_UTF8: u32 = 4;
+pub const MTTS_256_COLORS: u32 = 8;
+pub const MTTS_SCREEN_READER: u32 = 64;
+pub const MTTS_TRUECOLOR: u32 = 256;
+
+/// Longest subnegotiation payload we will buffer. Anything longer is a
+/// broken or hostile client; the payload is dropped rather than grown.
+const MAX_SUBNEG_LEN: usize = 8192;
+
+/// Longest input line we will buffer before forcing it through. Matches
+/// the spirit of MAX_RAW_INPUT_LENGTH in /web/deltamud/src/structs.h.
+const MAX_LINE_LEN: usize = 4096;
+
+/// Something the parser pulled out of the client's byte stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelnetEvent {
+    /// A complete line of user input with IAC sequences and CR/LF removed.
+    /// Other whitespace is left for the reader to trim.
+    Line(String),
+    Will(u8),
+    Wont(u8),
+    Do(u8),
+    Dont(u8),
+    /// IAC SB <option> <payload> IAC SE, with IAC IAC unescaped.
+    Subnegotiation(u8, Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseState {
+    Data,
+    Iac,
+    Negotiate(u8),
+    SubOption,
+    SubData(u8),
+    SubIac(u8),
+}
+
+/// Byte-level telnet state machine. Feed it whatever the socket returned
+/// and it hands back the complete lines and negotiation commands found so
+/// far; partial sequences are carried over to the next call.
+pub struct TelnetParser {
+    state: ParseState,
+    line: Vec<u8>,
+    subneg: Vec<u8>,
+}
+
+impl TelnetParser {
+    pub fn new() -> Self {
+        TelnetParser {
+            state: ParseState::Data,
+            line: Vec::new(),
+            subneg: Vec::new(),
+        }
+    }
+
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<TelnetEvent> {
+        let mut events = Vec::new();
+
+        for &byte in bytes {
+            self.state = match self.state {
+                ParseState::Data => match byte {
+                    IAC => ParseState::Iac,
+                    b'\n' => {
+                        events.push(self.take_line());
+                        ParseState::Data
+                    }
+                    // CR is either half of CR LF or CR NUL; both end up as
+                    // a single line break once the LF arrives.
+                    b'\r' | 0 => ParseState::Data,
+                    _ => {
+                        self.push_data(byte, &mut events);
+                        ParseState::Data
+                    }
+                },
+                ParseState::Iac => match byte {
+                    IAC => {
+                        self.push_data(IAC, &mut events);
+                        ParseState::Data
+                    }
+                    WILL | WONT | DO | DONT => ParseState::Negotiate(byte),
+                    SB => ParseState::SubOption,
+                    // NOP, GA, AYT, etc. carry nothing we act on.
+                    _ => ParseState::Data,
+                },
+                ParseState::Negotiate(cmd) => {
+                    events.push(match cmd {
+                        WILL => TelnetEvent::Will(byte),
+                        WONT => TelnetEvent::Wont(byte),
+                        DO => TelnetEvent::Do(byte),
+                        _ => TelnetEvent::Dont(byte),
+                    });
+                    ParseState::Data
+                }
+                ParseState::SubOption => {
+                    self.subneg.clear();
+                    ParseState::SubData(byte)
+                }
+                ParseState::SubData(opt) => match byte {
+                    IAC => ParseState::SubIac(opt),
+                    _ => {
+                        self.push_subneg(byte);
+                        ParseState::SubData(opt)
+                    }
+                },
+                ParseState::SubIac(opt) => match byte {
+                    SE => {
+                        let payload = std::mem::take(&mut self.subneg);
+                        events.push(TelnetEvent::Subnegotiation(opt, payload));
+                        ParseState::Data
+                    }
+                    IAC => {
+                        self.push_subneg(IAC);
+                        ParseState::SubData(opt)
+                    }
+                    // Malformed; abandon the subnegotiation.
+                    _ => {
+                        self.subneg.clear();
+                        ParseState::Data
+                    }
+                },
+            };
+        }
+
+        events
+    }
+
+    /// Anything left in the line buffer when the socket closes.
+    pub fn flush(&mut self) -> Option<TelnetEvent> {
+        if self.line.is_empty() {
+            None
+        } else {
+            Some(self.take_line())
+        }
+    }
+
+    fn push_data(&mut self, byte: u8, events: &mut Vec<TelnetEvent>) {
+        self.line.push(byte);
+        if self.line.len() >= MAX_LINE_LEN {
+            events.push(self.take_line());
+        }
+    }
+
+    fn push_subneg(&mut self, byte: u8) {
+        if self.subneg.len() < MAX_SUBNEG_LEN {
+            self.subneg.push(byte);
+        }
+    }
+
+    fn take_line(&mut self) -> TelnetEvent {
+        let raw = std::mem::take(&mut self.line);
+        TelnetEvent::Line(String::from_utf8_lossy(&raw).into_owned())
+    }
+}
+
+/// Which side of the connection performs an option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionSide {
+    /// We perform it (negotiated with WILL/WONT from us, DO/DONT from them).
+    Local,
+    /// The client performs it (WILL/WONT from them, DO/DONT from us).
+    Remote,
+}
+
+/// Result of processing a negotiation command: the bytes to answer with
+/// and, if the option actually flipped, its new state.
+#[derive(Debug, Default)]
+pub struct Negotiation {
+    pub reply: Option<Vec<u8>>,
+    pub changed: Option<(OptionSide, u8, bool)>,
+}
+
+/// Per-connection record of which telnet options are in effect. Follows
+/// the RFC 1143 rule of never answering a request that merely confirms
+/// the current state, which is what keeps two agreeable ends from
+/// looping forever.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelnetOptions {
+    local: HashSet<u8>,
+    remote: HashSet<u8>,
+    pending_local: HashSet<u8>,
+    pending_remote: HashSet<u8>,
+    /// Options this connection can't perform, whoever asks
+    #[serde(default)]
+    refused_local: HashSet<u8>,
+}
+
+impl TelnetOptions {
+    pub fn new() -> Self {
+        TelnetOptions::default()
+    }
+
+    pub fn local_enabled(&self, opt: u8) -> bool {
+        self.local.contains(&opt)
+    }
+
+    /// Never perform `opt` on this connection: offers of it are skipped
+    /// and a client's DO is answered WONT.
+    pub fn refuse_local(&mut self, opt: u8) {
+        self.refused_local.insert(opt);
+    }
+
+    /// Options the server agrees to perform when a client asks with DO.
+    /// Options we only ever offer ourselves are accepted through the
+    /// pending set instead.
+    fn accepts_local(&self, opt: u8) -> bool {
+        matches!(opt, TELOPT_SGA | TELOPT_MSDP | TELOPT_COMPRESS2 | TELOPT_GMCP)
+            && !self.refused_local.contains(&opt)
+    }
+
+    /// Options we are willing to let the client perform.
+    fn accepts_remote(opt: u8) -> bool {
+        matches!(opt, TELOPT_TTYPE | TELOPT_NAWS)
+    }
+
+    /// Offer to perform an option (IAC WILL). Returns the bytes to send,
+    /// or None if the option is already on or already being offered.
+    pub fn request_local(&mut self, opt: u8) -> Option<Vec<u8>> {
+        if self.refused_local.contains(&opt) || self.local.contains(&opt) || !self.pending_local.insert(opt) {
+            return None;
+        }
+        Some(command(WILL, opt))
+    }
+
+    /// Stop performing an option, or withdraw an offer the client has not
+    /// answered yet (IAC WONT).
+    pub fn disable_local(&mut self, opt: u8) -> Option<Vec<u8>> {
+        let was_pending = self.pending_local.remove(&opt);
+        if !self.local.remove(&opt) && !was_pending {
+            return None;
+        }
+        Some(command(WONT, opt))
+    }
+
+    /// Ask the client to perform an option (IAC DO).
+    pub fn request_remote(&mut self, opt: u8) -> Option<Vec<u8>> {
+        if self.remote.contains(&opt) || !self.pending_remote.insert(opt) {
+            return None;
+        }
+        Some(command(DO, opt))
+    }
+
+    /// Apply a WILL/WONT/DO/DONT received from the client.
+    pub fn receive(&mut self, event: &TelnetEvent) -> Negotiation {
+        let mut result = Negotiation::default();
+        match *event {
+            TelnetEvent::Will(opt) => {
+                let pending = self.pending_remote.remove(&opt);
+                if self.remote.contains(&opt) {
+                    return result;
+                }
+                if pending || Self::accepts_remote(opt) {
+                    self.remote.insert(opt);
+                    result.changed = Some((OptionSide::Remote, opt, true));
+                    if !pending {
+                        result.reply = Some(command(DO, opt));
+                    }
+                } else {
+                    result.reply = Some(command(DONT, opt));
+                }
+            }
+            TelnetEvent::Wont(opt) => {
+                let pending = self.pending_remote.remove(&opt);
+                if self.remote.remove(&opt) {
+                    result.changed = Some((OptionSide::Remote, opt, false));
+                    if !pending {
+                        result.reply = Some(command(DONT, opt));
+                    }
+                }
+            }
+            TelnetEvent::Do(opt) => {
+                let pending = self.pending_local.remove(&opt);
+                if self.local.contains(&opt) {
+                    return result;
+                }
+                if pending || self.accepts_local(opt) {
+                    self.local.insert(opt);
+                    result.changed = Some((OptionSide::Local, opt, true));
+                    if !pending {
+                        result.reply = Some(command(WILL, opt));
+                    }
+                } else {
+                    result.reply = Some(command(WONT, opt));
+                }
+            }
+            TelnetEvent::Dont(opt) => {
+                let pending = self.pending_local.remove(&opt);
+                if self.local.remove(&opt) {
+                    result.changed = Some((OptionSide::Local, opt, false));
+                    if !pending {
+                        result.reply = Some(command(WONT, opt));
+                    }
+                }
+            }
+            _ => {}
+        }
+        result
+    }
+}
+
+/// Build a three-byte IAC <cmd> <option> sequence.
+pub fn command(cmd: u8, opt: u8) -> Vec<u8> {
+    vec![IAC, cmd, opt]
+}
+
+/// Build IAC SB <option> <payload> IAC SE, doubling any IAC in the payload.
+pub fn subnegotiation(opt: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 5);
+    out.extend_from_slice(&[IAC, SB, opt]);
+    for &b in payload {
+        out.push(b);
+        if b == IAC {
+            out.push(IAC);
+        }
+    }
+    out.extend_from_slice(&[IAC, SE]);
+    out
+}
+
+/// How much color a client can render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ColorLevel {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// What we have learned about the client's terminal from NAWS and
+/// TERMINAL-TYPE. Until the client answers we assume the classic 80x24
+/// ANSI terminal the C server always wrote for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    pub width: u16,
+    pub height: u16,
+    pub client_name: Option<String>,
+    pub terminal_type: Option<String>,
+    pub mtts: Option<u32>,
+    pub color: ColorLevel,
+    pub utf8: bool,
+    pub screen_reader: bool,
+    ttype_round: u8,
+}
+
+impl TerminalInfo {
+    pub fn new() -> Self {
+        TerminalInfo {
+            width: 80,
+            height: 24,
+            client_name: None,
+            terminal_type: None,
+            mtts: None,
+            color: ColorLevel::Ansi16,
+            utf8: false,
+            screen_reader: false,
+            ttype_round: 0,
+        }
+    }
+
+    /// Apply a NAWS payload: width and height as two 16-bit big-endian
+    /// values. Zero means "unknown" and leaves the current value alone.
+    pub fn apply_naws(&mut self, payload: &[u8]) {
+        if payload.len() < 4 {
+            return;
+        }
+        let width = u16::from_be_bytes([payload[0], payload[1]]);
+        let height = u16::from_be_bytes([payload[2], payload[3]]);
+        if width > 0 {
+            self.width = width;
+        }
+        if height > 0 {
+            self.height = height;
+        }
+    }
+
+    /// Apply a TERMINAL-TYPE IS reply. MTTS clients answer successive SEND
+    /// requests with the client name, the terminal type and finally
+    /// "MTTS <bitvector>"; clients that don't cycle repeat the same
+    /// string. Returns true if another SEND should be issued.
+    pub fn apply_ttype(&mut self, payload: &[u8]) -> bool {
+        if payload.first() != Some(&TTYPE_IS) {
+            return false;
+        }
+        let name = String::from_utf8_lossy(&payload[1..]).trim().to_uppercase();
+        self.ttype_round += 1;
+
+        if let Some(bits) = name.strip_prefix("MTTS ") {
+            if let Ok(bits) = bits.trim().parse::<u32>() {
+                self.apply_mtts(bits);
+            }
+            return false;
+        }
+
+        match self.ttype_round {
+            1 => {
+                self.color = Self::color_from_name(&name).unwrap_or(self.color);
+                self.client_name = Some(name);
+                true
+            }
+            2 => {
+                // A repeat of the first answer means the client doesn't cycle.
+                if self.client_name.as_deref() == Some(name.as_str()) {
+                    return false;
+                }
+                self.color = Self::color_from_name(&name).unwrap_or(self.color);
+                self.terminal_type = Some(name);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn apply_mtts(&mut self, bits: u32) {
+        self.mtts = Some(bits);
+        self.utf8 = bits & MTTS_UTF8 != 0;
+        self.screen_reader = bits & MTTS_SCREEN_READER != 0;
+        self.color = if self.screen_reader {
+            ColorLevel::None
+        } else if bits & MTTS_TRUECOLOR != 0 {
+            ColorLevel::TrueColor
+        } else if bits & MTTS_256_COLORS != 0 {
+            ColorLevel::Ansi256
+        } else if bits & MTTS_ANSI != 0 {
+            ColorLevel::Ansi16
+        } else {
+            ColorLevel::None
+        };
+    }
+
+    /// Guess color depth from a client or terminal name, for clients that
+    /// stop before the MTTS round.
+    fn color_from_name(name: &str) -> Option<ColorLevel> {
+        if name.contains("TRUECOLOR") || name.ends_with("-DIRECT") {
+            Some(ColorLevel::TrueColor)
+        } else if name.contains("256COLOR") {
+            Some(ColorLevel::Ansi256)
+        } else if name == "DUMB" {
+            Some(ColorLevel::None)
+        } else {
+            None
+        }
+    }
+}
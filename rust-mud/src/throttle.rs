@@ -0,0 +1,67 @@
+// Connection rate limiting
+//
+// Caps how many connections one address may open per minute, so a bot
+// hammering the port gets dropped at accept time instead of costing a
+// Connection, a telnet negotiation and a login prompt each time. The cap
+// on simultaneous connections per address lives in Game, which knows
+// what's open.
+
+use log::warn;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Past this many tracked addresses, stale ones are swept out.
+const SWEEP_AT: usize = 1024;
+
+pub struct ConnectRate {
+    per_minute: usize,
+    recent: HashMap<IpAddr, VecDeque<Instant>>,
+}
+
+impl ConnectRate {
+    /// `per_minute` of 0 turns the limit off.
+    pub fn new(per_minute: usize) -> Self {
+        ConnectRate {
+            per_minute,
+            recent: HashMap::new(),
+        }
+    }
+
+    /// Record a connection attempt from `ip`, returning whether it's under
+    /// the limit. Refused attempts count too, so a bot that keeps
+    /// retrying stays locked out until it backs off for a minute.
+    pub fn allow(&mut self, ip: IpAddr) -> bool {
+        if self.per_minute == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        if self.recent.len() > SWEEP_AT {
+            self.recent.retain(|_, times| times.back().is_some_and(|t| now - *t < WINDOW));
+        }
+
+        let times = self.recent.entry(ip).or_default();
+        while times.front().is_some_and(|t| now - *t >= WINDOW) {
+            times.pop_front();
+        }
+        let allowed = times.len() < self.per_minute;
+        // Only the last per_minute attempts matter
+        if times.len() >= self.per_minute {
+            times.pop_front();
+        }
+        times.push_back(now);
+        allowed
+    }
+}
+
+/// Whether the accept loop should go ahead with a connection from `addr`.
+pub fn admit(rate: &Mutex<ConnectRate>, addr: SocketAddr) -> bool {
+    if rate.lock().allow(addr.ip()) {
+        return true;
+    }
+    warn!("Connection rate limit hit by [{}], dropping", addr.ip());
+    false
+}
@@ -0,0 +1,53 @@
+// TLS listener support
+//
+// Telnet sends passwords in cleartext, so an optional second port wraps
+// accepted sockets in TLS before they reach handle_client. Everything above
+// the socket (telnet negotiation, MCCP2, GMCP) is unchanged; most MUD
+// clients support "telnet over SSL" out of the box.
+
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+/// How long a client gets to finish the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build an acceptor from a PEM certificate chain and private key.
+pub fn load_acceptor(cert_path: &str, key_path: &str) -> Result<TlsAcceptor> {
+    let mut cert_reader = BufReader::new(
+        File::open(cert_path).with_context(|| format!("opening TLS certificate {}", cert_path))?,
+    );
+    let certs = rustls_pemfile::certs(&mut cert_reader)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("reading TLS certificate {}", cert_path))?;
+    if certs.is_empty() {
+        return Err(anyhow!("no certificates found in {}", cert_path));
+    }
+
+    let mut key_reader = BufReader::new(
+        File::open(key_path).with_context(|| format!("opening TLS key {}", key_path))?,
+    );
+    let key = rustls_pemfile::private_key(&mut key_reader)
+        .with_context(|| format!("reading TLS key {}", key_path))?
+        .ok_or_else(|| anyhow!("no private key found in {}", key_path))?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Run the TLS handshake on an accepted socket, giving up on a client
+/// that stalls partway so it can't hold the task open forever.
+pub async fn accept(acceptor: &TlsAcceptor, stream: TcpStream) -> Result<TlsStream<TcpStream>> {
+    timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+        .await
+        .map_err(|_| anyhow!("timed out waiting for TLS handshake"))?
+        .map_err(Into::into)
+}
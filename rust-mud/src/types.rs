@@ -14,6 +14,11 @@ pub type ArmorClass = i16;
 pub type Gold = i32;
 pub type Experience = i64;
 
+/// Lowest immortal level
+pub const LVL_IMMORT: Level = 31;
+/// Immortals from here up get through locks without a key
+pub const LVL_GOD: Level = 33;
+
 // Direction constants
 pub const NORTH: usize = 0;
 pub const EAST: usize = 1;
@@ -23,6 +28,15 @@ pub const UP: usize = 4;
 pub const DOWN: usize = 5;
 pub const NUM_OF_DIRS: usize = 6;
 
+/// Direction names, by direction constant (dirs)
+pub const DIRS: [&str; NUM_OF_DIRS] = ["north", "east", "south", "west", "up", "down"];
+
+/// The direction leading back the way `dir` went (rev_dir)
+pub const REV_DIR: [usize; NUM_OF_DIRS] = [SOUTH, WEST, NORTH, EAST, DOWN, UP];
+
+// Player flags, kept in act_flags on PCs (PLR_x in /web/deltamud/src/structs.h)
+pub const PLR_SITEOK: i64 = 1 << 7;
+
 // Class constants
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -53,9 +67,10 @@ pub enum Race {
 }
 
 // Position states
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 #[repr(u8)]
 pub enum Position {
+    #[default]
     Dead = 0,
     MortalllyWounded = 1,
     Incapacitated = 2,
@@ -68,6 +83,24 @@ pub enum Position {
     Standing = 9,
 }
 
+impl Position {
+    /// The position stored as `n` in the lib files, clamped into range.
+    pub fn from_i32(n: i32) -> Position {
+        match n {
+            i32::MIN..=0 => Position::Dead,
+            1 => Position::MortalllyWounded,
+            2 => Position::Incapacitated,
+            3 => Position::Stunned,
+            4 => Position::Sleeping,
+            5 => Position::Meditating,
+            6 => Position::Resting,
+            7 => Position::Sitting,
+            8 => Position::Fighting,
+            _ => Position::Standing,
+        }
+    }
+}
+
 // Gender
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
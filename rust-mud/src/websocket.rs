@@ -0,0 +1,108 @@
+// WebSocket transport for browser clients
+//
+// Browsers can't open raw TCP sockets, so the web client connects here
+// instead. Each session becomes an ordinary Connection on the same
+// GameMessage channel as a telnet player. Game text goes out as text
+// frames with color codes already rendered to ANSI; telnet protocol bytes
+// (option negotiation, GMCP, MSDP) go out as binary frames, so a minimal
+// client can ignore them and a richer one can parse them. Incoming text
+// frames are command lines and incoming binary frames go through the same
+// telnet parser as a raw socket, which lets the client answer negotiation
+// and send GMCP.
+
+use crate::connection::{
+    forward_event, GameMessage, Outbound, Transport, OUTPUT_QUEUE_SIZE, WELCOME_MESSAGE,
+};
+use crate::telnet::TelnetParser;
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use std::net::SocketAddr;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+pub async fn handle_websocket(
+    stream: TcpStream,
+    addr: SocketAddr,
+    conn_id: u64,
+    game_tx: mpsc::Sender<GameMessage>,
+) -> Result<()> {
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut sink, mut source) = ws.split();
+
+    // Create output channel
+    let (output_tx, mut output_rx) = mpsc::channel(OUTPUT_QUEUE_SIZE);
+
+    // Notify game of new connection
+    game_tx.send(GameMessage::NewConnection {
+        id: conn_id,
+        addr,
+        output_tx: output_tx.clone(),
+        transport: Transport::WebSocket,
+        fd: None,
+    }).await?;
+
+    output_tx.send(Outbound::Text(WELCOME_MESSAGE.to_string())).await?;
+
+    // Spawn task to handle output
+    let mut write_handle = tokio::spawn(async move {
+        while let Some(msg) = output_rx.recv().await {
+            let frame = match msg {
+                Outbound::Text(text) => Message::Text(text),
+                Outbound::Raw(bytes) => Message::Binary(bytes),
+                Outbound::Close => break,
+                Outbound::Flush(ack) => {
+                    let _ = ack.send(());
+                    continue;
+                }
+                // MCCP2 is never offered here, and tokio-tungstenite has
+                // no permessage-deflate, so WebSocket output is uncompressed.
+                Outbound::StartCompression | Outbound::EndCompression => continue,
+            };
+            if sink.send(frame).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    // Stop reading when the writer stops too, since that means the game
+    // hung up.
+    let mut parser = TelnetParser::new();
+    loop {
+        let frame = tokio::select! {
+            frame = source.next() => match frame {
+                Some(frame) => frame,
+                None => break,
+            },
+            _ = &mut write_handle => break,
+        };
+        let events = match frame {
+            Ok(Message::Text(text)) => {
+                // One frame is one command; the line terminator is implied.
+                let mut line = text.into_bytes();
+                if !line.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                parser.parse(&line)
+            }
+            Ok(Message::Binary(bytes)) => parser.parse(&bytes),
+            Ok(Message::Close(_)) | Err(_) => break,
+            // Pings are answered by tungstenite itself
+            Ok(_) => continue,
+        };
+        for event in events {
+            forward_event(&game_tx, conn_id, event).await?;
+        }
+    }
+    if let Some(event) = parser.flush() {
+        forward_event(&game_tx, conn_id, event).await?;
+    }
+
+    // Notify disconnection
+    game_tx.send(GameMessage::Disconnect { conn_id }).await?;
+
+    // Cleanup
+    write_handle.abort();
+    Ok(())
+}
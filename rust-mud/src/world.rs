@@ -1,5 +1,5 @@
 use crate::types::*;
-use crate::room::{Room, Exit};
+use crate::room::{Room, Exit, EX_CLOSED, EX_LOCKED};
 use crate::object::{Object, ObjectType, WearFlags, ExtraFlags};
 use crate::character::Character;
 use std::collections::HashMap;
@@ -122,6 +122,31 @@ impl World {
         self.rooms.get(&vnum).cloned()
     }
     
+    /// Change the flags of the door leading `dir` from `room_vnum`, and of
+    /// the same door seen from the other side, so the two never disagree.
+    /// The far side counts only if its exit leads straight back. Returns
+    /// false if there's no such exit.
+    pub fn update_door(&self, room_vnum: RoomVnum, dir: usize, update: impl Fn(&mut i32)) -> bool {
+        let to_room = match self.get_room(room_vnum) {
+            Some(room) => match room.write().exits.get_mut(dir).and_then(|e| e.as_mut()) {
+                Some(exit) => {
+                    update(&mut exit.exit_info);
+                    exit.to_room
+                }
+                None => return false,
+            },
+            None => return false,
+        };
+        if let Some(other) = self.get_room(to_room) {
+            if let Some(back) = other.write().exits[REV_DIR[dir]].as_mut() {
+                if back.to_room == room_vnum {
+                    update(&mut back.exit_info);
+                }
+            }
+        }
+        true
+    }
+
     pub fn add_room(&mut self, room: Room) {
         let vnum = room.number;
         self.rooms.insert(vnum, Arc::new(RwLock::new(room)));
@@ -419,22 +444,16 @@ impl World {
                     }
                 }
                 ResetCmd::Door { room_vnum, direction, state, .. } => {
-                    if let Some(room) = self.get_room(*room_vnum) {
-                        if let Some(exit) = room.write().exits.get_mut(*direction).and_then(|e| e.as_mut()) {
-                            // exit_info bits: 1=CLOSED, 2=LOCKED (matches CircleMUD EX_*).
-                            exit.exit_info = match *state {
-                                0 => 0,          // open
-                                1 => 1,          // closed
-                                2 => 1 | 2,      // closed & locked
-                                _ => exit.exit_info,
-                            };
-                            summary.doors_set += 1;
-                            last_cmd = true;
-                        } else {
-                            last_cmd = false;
-                        }
-                    } else {
-                        last_cmd = false;
+                    // state: 0 open, 1 closed, 2 closed and locked
+                    let (set, clear) = match *state {
+                        0 => (0, EX_CLOSED | EX_LOCKED),
+                        1 => (EX_CLOSED, EX_LOCKED),
+                        2 => (EX_CLOSED | EX_LOCKED, 0),
+                        _ => (0, 0),
+                    };
+                    last_cmd = self.update_door(*room_vnum, *direction, |info| *info = (*info | set) & !clear);
+                    if last_cmd {
+                        summary.doors_set += 1;
                     }
                 }
             }